@@ -0,0 +1,449 @@
+//! 命令行参数
+//!
+//! 不带子命令运行时与原先一致，完全由环境变量驱动：设置了 `exe_path` 时应用更新，
+//! 否则继续 `.running_status` 中记录的任务。子命令的参数未指定时依次回退到环境变量和配置文件。
+
+use std::{
+    env,
+    path::{self, Path, PathBuf},
+    process,
+};
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    config::{self, ConfigFile},
+    logging::{self, Log, Logger},
+    update::{
+        generate, maintenance,
+        status::{self, StatusReport},
+        verify::VerifyReport,
+        FailReason, HashAlgorithm, RunningState, TaskOptions,
+    },
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "updater", version, about = "electron 增量更新程序")]
+pub struct Cli {
+    /// 配置文件路径，未指定时读取环境变量 updater_config，
+    /// 再查找更新程序所在目录下的 updater.toml 或 updater.json
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 应用更新目录中暂存的更新，指定更新服务器地址时先下载更新
+    Apply(ApplyArgs),
+    /// 从更新服务器下载更新到更新目录，不应用
+    Download(ApplyArgs),
+    /// 继续 .running_status 中记录的未完成更新
+    Resume(TaskArgs),
+    /// 查看 .running_status 中记录的运行状态
+    Status(StatusArgs),
+    /// 回滚未完成或失败的更新
+    Rollback,
+    /// 检查暂存的更新是否完整，或按更新配置校验安装目录
+    Verify(VerifyArgs),
+    /// 清理已完成更新遗留的文件
+    Clean(TaskArgs),
+    /// 对比新旧两个版本，生成更新目录和更新配置
+    Generate(GenerateArgs),
+}
+
+#[derive(Args, Debug, Default)]
+pub struct TaskArgs {
+    /// electron 可执行文件路径，未指定时读取环境变量 exe_path
+    #[arg(long)]
+    pub exe_path: Option<PathBuf>,
+    /// 更新文件所在目录，未指定时读取环境变量 update_temp_path
+    #[arg(long)]
+    pub update_temp_path: Option<PathBuf>,
+    /// 更新配置文件名，未指定时读取环境变量 update_config_file_name
+    #[arg(long)]
+    pub update_config_file_name: Option<String>,
+    /// electron 主进程 pid，未指定时读取环境变量 exe_pid
+    #[arg(long)]
+    pub exe_pid: Option<usize>,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct ApplyArgs {
+    #[command(flatten)]
+    pub task: TaskArgs,
+    /// 更新服务器地址，未指定时读取环境变量 update_base_url
+    #[arg(long)]
+    pub base_url: Option<String>,
+    /// 镜像，可以指定多次，按顺序作为更新服务器的后备来源
+    #[arg(long = "mirror")]
+    pub mirrors: Vec<String>,
+    /// 发布渠道，例如 stable、beta、internal，未指定时读取环境变量 update_channel
+    #[arg(long)]
+    pub channel: Option<String>,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct StatusArgs {
+    /// 以 JSON 格式输出
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct VerifyArgs {
+    #[command(flatten)]
+    pub task: TaskArgs,
+    /// 校验安装目录中的文件，而不是暂存的更新
+    #[arg(long)]
+    pub installed: bool,
+    /// 用于校验安装目录的更新配置或全量版本清单，默认为更新目录中的更新配置
+    #[arg(long, requires = "installed")]
+    pub manifest: Option<PathBuf>,
+    /// 更新配置是全量版本清单，同时报告安装目录中多余的文件
+    #[arg(long, requires = "installed")]
+    pub full: bool,
+    /// 以 JSON 格式输出
+    #[arg(long, requires = "installed")]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct GenerateArgs {
+    /// 解包后的旧版本目录
+    #[arg(long)]
+    pub old: PathBuf,
+    /// 解包后的新版本目录
+    #[arg(long)]
+    pub new: PathBuf,
+    /// 输出的更新目录
+    #[arg(long)]
+    pub out: PathBuf,
+    /// 更新配置文件名
+    #[arg(long, default_value = "update-config.json")]
+    pub update_config_file_name: String,
+    /// 摘要算法：md5、sha1、sha256、sha512
+    #[arg(long, default_value = "sha256")]
+    pub hash: HashAlgorithm,
+}
+
+impl TaskArgs {
+    /// 转换为任务参数，相对路径按当前目录展开，未指定的项使用 `fallback` 中的值
+    pub fn into_options(self, fallback: TaskOptions) -> TaskOptions {
+        TaskOptions {
+            exe_path: self.exe_path.and_then(|p| path::absolute(p).ok()),
+            update_temp_path: self.update_temp_path.and_then(|p| path::absolute(p).ok()),
+            update_config_file_name: self.update_config_file_name,
+            exe_pid: self.exe_pid,
+            ..Default::default()
+        }
+        .or(fallback)
+    }
+}
+
+impl ApplyArgs {
+    pub fn into_options(self, fallback: TaskOptions) -> TaskOptions {
+        let mut options = self.task.into_options(fallback);
+        options.download.base_url = self.base_url.or(options.download.base_url);
+        options.download.channel = self.channel.or(options.download.channel);
+        if !self.mirrors.is_empty() {
+            options.download.mirrors = self.mirrors;
+        }
+        options
+    }
+}
+
+/// 解析命令行
+///
+/// 维护类子命令（`status`、`rollback`、`verify`、`clean`、`generate`、`download`）在这里执行完毕后直接退出进程，
+/// 其余情况返回更新任务的参数
+pub fn parse() -> TaskOptions {
+    // 带参数运行时（子命令、--help 等）需要在控制台中输出
+    if env::args_os().len() > 1 {
+        attach_console();
+    }
+    let cli = Cli::parse();
+    let config = load_config(cli.config.as_deref());
+    if let Some(dir) = &config.log.dir {
+        logging::set_log_dir(dir.clone());
+    }
+    let from_env = TaskOptions::from_env();
+    // 配置文件中的 exe_path 只是默认值，不带子命令运行时先继续未完成的任务，避免覆盖需要回滚的状态和备份
+    let resume = cli.command.is_none() && from_env.exe_path.is_none() && has_unfinished_task();
+    let fallback = from_env.or(config.task_options());
+    let options = match cli.command {
+        None if resume => {
+            Log::setup_logging();
+            Log::warn("存在未完成的更新任务，忽略配置文件中的 exe_path，继续 .running_status 中记录的任务");
+            TaskOptions {
+                exe_path: None,
+                ..fallback
+            }
+        }
+        None => fallback,
+        Some(Command::Apply(args)) => {
+            let options = args.into_options(fallback);
+            if options.exe_path.is_none() {
+                eprintln!("缺少 exe_path，请通过 --exe-path、环境变量 exe_path 或配置文件指定");
+                process::exit(FailReason::Config.exit_code());
+            }
+            options
+        }
+        Some(Command::Resume(args)) => TaskOptions {
+            exe_path: None,
+            ..args.into_options(fallback)
+        },
+        Some(command) => {
+            Log::setup_logging();
+            process::exit(run(command, fallback))
+        }
+    };
+    let problems = config::validate(&options, &config.log);
+    if !problems.is_empty() {
+        exit_with_problems(&problems);
+    }
+    options
+}
+
+/// `.running_status` 中是否记录了未完成或失败、需要继续或回滚的任务，文件无法读取时同样视为有
+fn has_unfinished_task() -> bool {
+    match maintenance::load_running_config() {
+        Ok(Some(config)) => matches!(
+            config.status,
+            RunningState::Updating | RunningState::UpdateButNotCheck | RunningState::Failed
+        ),
+        Ok(None) => false,
+        Err(_) => true,
+    }
+}
+
+/// 连接到启动更新程序的控制台
+///
+/// Windows 上更新程序使用 GUI 子系统，没有自己的控制台，子命令和无界面运行的输出默认不可见。
+/// 父进程没有控制台（例如由 electron 启动）时不做任何事，其他平台上标准输出总是可用
+#[cfg(windows)]
+pub fn attach_console() {
+    crate::win32::attach_parent_console();
+}
+
+#[cfg(not(windows))]
+pub fn attach_console() {}
+
+fn load_config(explicit: Option<&Path>) -> ConfigFile {
+    let Some(path) = config::locate(explicit) else {
+        return ConfigFile::default();
+    };
+    match config::load(&path) {
+        Ok(config) => config,
+        Err(problems) => exit_with_problems(&problems),
+    }
+}
+
+fn exit_with_problems(problems: &[String]) -> ! {
+    Log::setup_logging();
+    for problem in problems.iter() {
+        eprintln!("{problem}");
+        Log::error(problem);
+    }
+    process::exit(FailReason::Config.exit_code());
+}
+
+/// 执行维护类子命令，未指定的参数使用 `fallback` 中的值，返回进程退出码
+pub fn run(command: Command, fallback: TaskOptions) -> i32 {
+    let result = match command {
+        Command::Status(args) => status::inspect().map(|report| {
+            if args.json {
+                match serde_json::to_string_pretty(&report) {
+                    Ok(json) => println!("{json}"),
+                    Err(e) => eprintln!("序列化运行状态失败: {e}"),
+                }
+            } else {
+                match report {
+                    Some(report) => print_status(&report),
+                    None => println!("没有记录的更新任务"),
+                }
+            }
+        }),
+        Command::Rollback => maintenance::rollback().map(|rolled_back| {
+            if rolled_back {
+                println!("已回滚未完成的更新");
+            } else {
+                println!("没有需要回滚的更新");
+            }
+        }),
+        Command::Verify(args) if args.installed => {
+            let manifest = args.manifest.and_then(|p| path::absolute(p).ok());
+            maintenance::verify_installed(
+                &args.task.into_options(fallback),
+                manifest.as_deref(),
+                args.full,
+            )
+            .and_then(|report| {
+                if args.json {
+                    match serde_json::to_string_pretty(&report) {
+                        Ok(json) => println!("{json}"),
+                        Err(e) => eprintln!("序列化校验结果失败: {e}"),
+                    }
+                } else {
+                    print_verify_report(&report);
+                }
+                if report.is_ok() {
+                    Ok(())
+                } else {
+                    Err(FailReason::Integrity)
+                }
+            })
+        }
+        Command::Verify(args) => maintenance::verify_staging(&args.task.into_options(fallback))
+            .and_then(|missing| {
+                if missing.is_empty() {
+                    println!("暂存的更新完整");
+                    return Ok(());
+                }
+                for path in missing.iter() {
+                    println!("缺少文件: {}", path.to_string_lossy());
+                }
+                Err(FailReason::Integrity)
+            }),
+        Command::Clean(args) => maintenance::clean(&args.into_options(fallback)).map(|removed| {
+            for path in removed.iter() {
+                println!("已删除: {}", path.to_string_lossy());
+            }
+        }),
+        Command::Generate(args) => {
+            return match generate::generate(
+                &args.old,
+                &args.new,
+                &args.out,
+                &args.update_config_file_name,
+                args.hash,
+            ) {
+                Ok(report) => {
+                    println!(
+                        "新增 {} 个文件，变更 {} 个文件，更新配置: {}",
+                        report.config.added.len(),
+                        report.config.changed.len(),
+                        report.config_path.to_string_lossy()
+                    );
+                    for relative in report.removed.iter() {
+                        println!("新版本中已删除（更新时会保留）: {relative}");
+                    }
+                    0
+                }
+                Err(e) => {
+                    eprintln!("生成更新目录失败: {e}");
+                    1
+                }
+            };
+        }
+        Command::Download(args) => download(args.into_options(fallback)),
+        Command::Apply(_) | Command::Resume(_) => Ok(()),
+    };
+    match result {
+        Ok(_) => 0,
+        Err(reason) => {
+            eprintln!("执行失败: {reason:?}");
+            reason.exit_code()
+        }
+    }
+}
+
+#[cfg(feature = "download")]
+fn download(options: TaskOptions) -> Result<(), FailReason> {
+    use crate::update::{download, UpdateUi};
+
+    struct ConsoleUi;
+    impl UpdateUi for ConsoleUi {
+        fn on_file(&self, current: usize, total: usize, path: &Path) {
+            println!("({current}/{total}) {}", path.to_string_lossy());
+        }
+        fn on_quit(&self) {}
+    }
+
+    if options.download.base_url.is_none() {
+        eprintln!("缺少更新服务器地址，请通过 --base-url、环境变量 update_base_url 或配置文件指定");
+        return Err(FailReason::Config);
+    }
+    let Some(exe_path) = options.exe_path.as_deref() else {
+        eprintln!("缺少 exe_path，请通过 --exe-path、环境变量 exe_path 或配置文件指定");
+        return Err(FailReason::InstallDir);
+    };
+    let update_temp_path =
+        maintenance::install_dir(&options).map(|root| options.update_temp_path(&root))?;
+    download::download(
+        &ConsoleUi,
+        &options.download,
+        exe_path,
+        &update_temp_path,
+        options.update_config_file_name(),
+    )
+    .map(|report| match report {
+        Some(report) => {
+            println!(
+                "下载完成，新增 {} 个文件，变更 {} 个文件，更新配置来自 {}",
+                report.config.added.len(),
+                report.config.changed.len(),
+                report.manifest_source
+            );
+            for file in report.files.iter() {
+                match &file.source {
+                    Some(source) => println!("  {} 来自 {source}", file.hash),
+                    None => println!("  {} 已存在", file.hash),
+                }
+            }
+        }
+        None => println!("没有需要安装的更新"),
+    })
+}
+
+#[cfg(not(feature = "download"))]
+fn download(_options: TaskOptions) -> Result<(), FailReason> {
+    eprintln!("更新程序编译时未启用 download 特性");
+    Err(FailReason::Config)
+}
+
+fn print_status(report: &StatusReport) {
+    let yes_no = |value: bool| if value { "是" } else { "否" };
+    println!("状态: {:?}（{}）", report.status, report.description);
+    println!("exe_path: {}", report.exe_path);
+    println!("update_temp_path: {}", report.update_temp_path);
+    println!("已备份的旧文件 ({}):", report.backed_up.len());
+    for file in report.backed_up.iter() {
+        println!(
+            "  {} -> {} [原路径存在: {}, 备份存在: {}]",
+            file.path.to_string_lossy(),
+            file.backup_path.to_string_lossy(),
+            yes_no(file.path_exists),
+            yes_no(file.backup_exists)
+        );
+    }
+    println!("已写入的新文件 ({}):", report.written.len());
+    for file in report.written.iter() {
+        println!("  {} [存在: {}]", file.path.to_string_lossy(), yes_no(file.exists));
+    }
+}
+
+fn print_verify_report(report: &VerifyReport) {
+    println!("校验通过: {} 个文件", report.checked);
+    for path in report.missing.iter() {
+        println!("缺少: {}", path.to_string_lossy());
+    }
+    for file in report.modified.iter() {
+        println!(
+            "已修改: {} (期望 {}, 实际 {})",
+            file.path.to_string_lossy(),
+            file.expected,
+            file.actual
+        );
+    }
+    for path in report.unexpected.iter() {
+        println!("多余: {}", path.to_string_lossy());
+    }
+    for path in report.unverifiable.iter() {
+        println!("无法校验: {}", path.to_string_lossy());
+    }
+    for file in report.unreadable.iter() {
+        println!("无法读取: {} ({})", file.path.to_string_lossy(), file.error);
+    }
+}
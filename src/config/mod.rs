@@ -0,0 +1,476 @@
+//! 更新程序的配置文件
+//!
+//! 配置文件按以下顺序查找，找到第一个即停止：
+//!
+//! 1. 命令行参数 `--config`；
+//! 2. 环境变量 `updater_config`；
+//! 3. 更新程序所在目录下的 `updater.toml` 或 `updater.json`。
+//!
+//! 同一项配置的优先级为：命令行、环境变量、配置文件、默认值。开始更新前会检查无法识别的配置项
+//! 和无效的路径，发现问题时不做任何更新，以 `FailReason::Config` 对应的退出码退出。
+//!
+//! ```toml
+//! exe_path = "/opt/app/electron"
+//! update_temp_path = "/opt/app/update_temp"
+//! update_config_file_name = "update-config.json"
+//!
+//! [timeouts]
+//! kill_wait_ms = 50
+//! self_update_wait_ms = 500
+//!
+//! [relaunch]
+//! enabled = true
+//! args = ["--updated"]
+//!
+//! [log]
+//! dir = "log"
+//!
+//! [ui]
+//! title = "更新程序"
+//! locale = "zh-CN"
+//! app_name = "MyApp"
+//! # 或 "light"、"system"（跟随系统）
+//! appearance = "dark"
+//! # 应用更新前询问用户
+//! confirm = true
+//!
+//! [ui.dark]
+//! accent = "#3b82f6"
+//!
+//! [download]
+//! base_url = "https://example.com/updates/1.2.0"
+//! mirrors = ["https://mirror.example.com/updates/1.2.0", "/mnt/share/updates/1.2.0"]
+//! # 或 "electron-builder"，读取 latest-linux.yml 和 .blockmap
+//! format = "manifest"
+//! channel = "stable"
+//! retries = 5
+//! retry_delay_ms = 500
+//! proxy = "http://proxy.example.com:8080"
+//! no_proxy = ["localhost", ".lan"]
+//! ca_bundle = "/etc/ssl/certs/corp-ca.pem"
+//! ```
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde_derive::Deserialize;
+use serde_json::Value;
+
+use crate::i18n::Locale;
+use crate::update::{
+    parse_color, DownloadFormat, DownloadOptions, RelaunchOptions, TaskOptions, Timeouts, UiOptions,
+};
+
+const FILE_NAMES: [&str; 2] = ["updater.toml", "updater.json"];
+
+/// `ui.light`、`ui.dark` 中的颜色项
+const PALETTE_KEYS: &[&str] = &[
+    "background",
+    "card",
+    "border",
+    "text",
+    "text_muted",
+    "text_hint",
+    "track",
+    "accent",
+    "accent_text",
+    "success",
+    "success_text",
+    "danger",
+];
+
+/// 已知的配置项，键为所在的表（嵌套的表以 `.` 连接），空字符串表示顶层
+const KNOWN_KEYS: &[(&str, &[&str])] = &[
+    (
+        "",
+        &[
+            "exe_path",
+            "update_temp_path",
+            "update_config_file_name",
+            "exe_pid",
+            "timeouts",
+            "relaunch",
+            "log",
+            "ui",
+            "download",
+        ],
+    ),
+    ("timeouts", &["kill_wait_ms", "self_update_wait_ms"]),
+    ("relaunch", &["enabled", "args"]),
+    ("log", &["dir"]),
+    (
+        "ui",
+        &[
+            "title",
+            "locale",
+            "app_name",
+            "logo",
+            "width",
+            "height",
+            "appearance",
+            "light",
+            "dark",
+            "confirm",
+        ],
+    ),
+    ("ui.light", PALETTE_KEYS),
+    ("ui.dark", PALETTE_KEYS),
+    (
+        "download",
+        &[
+            "base_url",
+            "mirrors",
+            "format",
+            "channel",
+            "artifact",
+            "current_version",
+            "retries",
+            "retry_delay_ms",
+            "proxy",
+            "no_proxy",
+            "ca_bundle",
+        ],
+    ),
+];
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct LogConfig {
+    /// 日志目录，相对路径相对于工作目录
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ConfigFile {
+    pub exe_path: Option<PathBuf>,
+    pub update_temp_path: Option<PathBuf>,
+    pub update_config_file_name: Option<String>,
+    pub exe_pid: Option<usize>,
+    pub timeouts: Timeouts,
+    pub relaunch: RelaunchOptions,
+    pub log: LogConfig,
+    pub ui: UiOptions,
+    pub download: DownloadOptions,
+}
+
+impl ConfigFile {
+    /// 转换为任务参数，作为命令行和环境变量之后的回退
+    pub fn task_options(&self) -> TaskOptions {
+        TaskOptions {
+            exe_path: self.exe_path.clone(),
+            update_temp_path: self.update_temp_path.clone(),
+            update_config_file_name: self.update_config_file_name.clone(),
+            exe_pid: self.exe_pid,
+            timeouts: self.timeouts.clone(),
+            relaunch: self.relaunch.clone(),
+            ui: self.ui.clone(),
+            download: self.download.clone(),
+        }
+    }
+}
+
+/// 查找配置文件，未找到时返回 `None`
+pub fn locate(explicit: Option<&Path>) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(path.to_owned());
+    }
+    if let Ok(path) = env::var("updater_config") {
+        return Some(PathBuf::from(path));
+    }
+    let dir = env::current_exe().ok()?.parent()?.to_owned();
+    FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// 读取配置文件，`.json` 结尾的按 JSON 解析，其余按 TOML 解析
+///
+/// `exe_path`、`update_temp_path`、`download.ca_bundle`、`ui.logo` 为相对路径时相对于配置文件所在目录；
+/// 返回的错误列表包含所有无法识别的配置项，而不只是第一个
+pub fn load(path: &Path) -> Result<ConfigFile, Vec<String>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| vec![format!("读取配置文件 {} 失败: {e}", path.to_string_lossy())])?;
+    let value: Value = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&content).map_err(|e| vec![format!("配置文件不是有效的 JSON: {e}")])?
+    } else {
+        toml::from_str(&content).map_err(|e| vec![format!("配置文件不是有效的 TOML: {e}")])?
+    };
+    let unknown = unknown_keys(&value);
+    if !unknown.is_empty() {
+        return Err(unknown
+            .into_iter()
+            .map(|key| format!("无法识别的配置项: {key}"))
+            .collect());
+    }
+    let mut config: ConfigFile =
+        serde_json::from_value(value).map_err(|e| vec![format!("配置项的值无效: {e}")])?;
+    let dir = std::path::absolute(path)
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    for path in [
+        &mut config.exe_path,
+        &mut config.update_temp_path,
+        &mut config.download.ca_bundle,
+        &mut config.ui.logo,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if path.is_relative() {
+            *path = dir.join(&*path);
+        }
+    }
+    Ok(config)
+}
+
+fn unknown_keys(value: &Value) -> Vec<String> {
+    let mut unknown = Vec::new();
+    collect_unknown_keys("", value, &mut unknown);
+    unknown
+}
+
+fn collect_unknown_keys(table: &str, value: &Value, unknown: &mut Vec<String>) {
+    let Some(map) = value.as_object() else {
+        return;
+    };
+    let known = known_keys(table);
+    for (key, child) in map.iter() {
+        let path = if table.is_empty() {
+            key.clone()
+        } else {
+            format!("{table}.{key}")
+        };
+        if !known.contains(&key.as_str()) {
+            unknown.push(path);
+        } else if child.is_object() {
+            collect_unknown_keys(&path, child, unknown);
+        }
+    }
+}
+
+fn known_keys(table: &str) -> &'static [&'static str] {
+    KNOWN_KEYS
+        .iter()
+        .find(|(name, _)| *name == table)
+        .map(|(_, keys)| *keys)
+        .unwrap_or(&[])
+}
+
+/// 在开始更新前检查合并后的参数，返回所有发现的问题
+pub fn validate(options: &TaskOptions, log: &LogConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(path) = &options.exe_path {
+        if !path.is_absolute() {
+            problems.push(format!("exe_path 必须是绝对路径: {}", path.to_string_lossy()));
+        } else if !path.is_file() {
+            problems.push(format!("exe_path 不存在: {}", path.to_string_lossy()));
+        }
+    }
+    if let Some(path) = &options.update_temp_path {
+        if !path.is_absolute() {
+            problems.push(format!("update_temp_path 必须是绝对路径: {}", path.to_string_lossy()));
+        } else if path.exists() && !path.is_dir() {
+            problems.push(format!("update_temp_path 不是目录: {}", path.to_string_lossy()));
+        }
+    }
+    if let Some(name) = &options.update_config_file_name {
+        if name.is_empty() || Path::new(name).components().count() != 1 {
+            problems.push(format!("update_config_file_name 必须是文件名: {name}"));
+        }
+    }
+    if let Some(url) = &options.download.base_url {
+        if !cfg!(feature = "download") {
+            problems
+                .push("设置了 download.base_url，但更新程序编译时未启用 download 特性".to_string());
+        } else if !is_source(url) {
+            problems.push(format!(
+                "download.base_url 必须是 http(s) 地址、file:// 地址或绝对路径: {url}"
+            ));
+        }
+        if options.download.format == DownloadFormat::ElectronBuilder
+            && options.download.current_version.is_none()
+        {
+            problems.push(
+                "download.format 为 electron-builder 时需要设置 download.current_version \
+                 或环境变量 update_current_version"
+                    .to_string(),
+            );
+        }
+    }
+    for mirror in options.download.mirrors.iter() {
+        if !is_source(mirror) {
+            problems.push(format!(
+                "download.mirrors 必须是 http(s) 地址、file:// 地址或绝对路径: {mirror}"
+            ));
+        }
+    }
+    if options.download.base_url.is_none() && !options.download.mirrors.is_empty() {
+        problems.push("设置了 download.mirrors 但没有设置 download.base_url".to_string());
+    }
+    if let Some(channel) = &options.download.channel {
+        if channel.is_empty()
+            || !channel
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        {
+            problems.push(format!("download.channel 只能包含字母、数字、- 和 _: {channel}"));
+        }
+    }
+    if let Some(artifact) = &options.download.artifact {
+        if artifact.is_absolute() {
+            problems.push(format!(
+                "download.artifact 必须是相对于安装目录的路径: {}",
+                artifact.to_string_lossy()
+            ));
+        }
+    }
+    if let Some(proxy) = &options.download.proxy {
+        if ![
+            "http://",
+            "https://",
+            "socks4://",
+            "socks4a://",
+            "socks5://",
+        ]
+        .iter()
+        .any(|scheme| proxy.starts_with(scheme))
+        {
+            problems
+                .push(format!("download.proxy 必须是 http(s)、socks4(a) 或 socks5 地址: {proxy}"));
+        }
+    }
+    if !options.download.no_proxy.is_empty() && options.download.proxy.is_none() {
+        problems.push("设置了 download.no_proxy 但没有设置 download.proxy".to_string());
+    }
+    if let Some(bundle) = &options.download.ca_bundle {
+        if !bundle.is_file() {
+            problems
+                .push(format!("download.ca_bundle 不存在或不是文件: {}", bundle.to_string_lossy()));
+        }
+    }
+    if let Some(locale) = &options.ui.locale {
+        if Locale::parse(locale).is_none() {
+            problems.push(format!("ui.locale 不受支持，可选 zh-CN、en-US: {locale}"));
+        }
+    }
+    if let Some(logo) = &options.ui.logo {
+        if !logo.is_file() {
+            problems.push(format!("ui.logo 不存在: {}", logo.to_string_lossy()));
+        }
+    }
+    for (name, size) in [("width", options.ui.width), ("height", options.ui.height)] {
+        if size.is_some_and(|size| !(160.0..=4096.0).contains(&size)) {
+            problems.push(format!("ui.{name} 必须在 160 到 4096 之间"));
+        }
+    }
+    for (table, palette) in [("light", &options.ui.light), ("dark", &options.ui.dark)] {
+        for (name, color) in palette.entries() {
+            if let Some(color) = color.filter(|color| parse_color(color).is_none()) {
+                problems.push(format!(
+                    "ui.{table}.{name} 必须是 #rrggbb 或 #rrggbbaa 格式的颜色: {color}"
+                ));
+            }
+        }
+    }
+    if let Some(dir) = &log.dir {
+        if dir.exists() && !dir.is_dir() {
+            problems.push(format!("log.dir 不是目录: {}", dir.to_string_lossy()));
+        }
+    }
+    problems
+}
+
+/// 是否是可用的下载来源：http(s) 地址、`file://` 地址或本地目录的绝对路径
+fn is_source(location: &str) -> bool {
+    location.starts_with("http://")
+        || location.starts_with("https://")
+        || location.starts_with("file://")
+        || Path::new(location).is_absolute()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::update::test_support::TempDir;
+
+    #[test]
+    fn reports_every_unknown_key() {
+        let value = serde_json::json!({
+            "exe_path": "/opt/app/electron",
+            "exe_pth": "/opt/app/electron",
+            "ui": { "title": "Updater", "dark": { "accent": "#000000", "accnet": "#000000" } },
+            "download": { "base_url": "https://example.com", "retry": 3 },
+        });
+        let mut unknown = unknown_keys(&value);
+        unknown.sort();
+        assert_eq!(unknown, ["download.retry", "exe_pth", "ui.dark.accnet"]);
+    }
+
+    #[test]
+    fn loads_relative_paths_from_config_dir() {
+        let dir = TempDir::new("config");
+        let path = dir.write(
+            "updater.toml",
+            b"exe_path = \"app/electron\"\nupdate_temp_path = \"/var/tmp/update\"\n\n\
+              [download]\nbase_url = \"https://example.com/updates\"\nretries = 2\n",
+        );
+        let config = load(&path).unwrap();
+        assert_eq!(config.exe_path, Some(dir.path().join("app/electron")));
+        assert_eq!(config.update_temp_path, Some(PathBuf::from("/var/tmp/update")));
+        assert_eq!(config.download.retries, 2);
+        assert_eq!(config.download.retry_delay_ms, DownloadOptions::default().retry_delay_ms);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_when_loading() {
+        let dir = TempDir::new("config");
+        let path = dir.write("updater.json", br#"{"timeouts": {"kill_wait": 1}, "lgo": {}}"#);
+        let mut errors = load(&path).unwrap_err();
+        errors.sort();
+        assert_eq!(
+            errors,
+            [
+                "无法识别的配置项: lgo",
+                "无法识别的配置项: timeouts.kill_wait"
+            ]
+        );
+    }
+
+    #[test]
+    fn validates_merged_options() {
+        let mut options = TaskOptions {
+            exe_path: Some(PathBuf::from("electron")),
+            update_config_file_name: Some("dir/update-config.json".to_string()),
+            ..Default::default()
+        };
+        options.download.mirrors = vec!["https://mirror.example.com".to_string()];
+        options.download.channel = Some("beta/1".to_string());
+        options.download.no_proxy = vec!["localhost".to_string()];
+        options.ui.locale = Some("de-DE".to_string());
+        options.ui.width = Some(100.0);
+        options.ui.dark.accent = Some("blue".to_string());
+        let problems = validate(&options, &LogConfig::default());
+        for expected in [
+            "exe_path 必须是绝对路径",
+            "update_config_file_name 必须是文件名",
+            "设置了 download.mirrors 但没有设置 download.base_url",
+            "download.channel 只能包含",
+            "设置了 download.no_proxy 但没有设置 download.proxy",
+            "ui.locale 不受支持",
+            "ui.width 必须在 160 到 4096 之间",
+            "ui.dark.accent 必须是",
+        ] {
+            assert!(
+                problems.iter().any(|problem| problem.starts_with(expected)),
+                "缺少 {expected}: {problems:?}"
+            );
+        }
+        assert_eq!(problems.len(), 8, "{problems:?}");
+        assert!(validate(&TaskOptions::default(), &LogConfig::default()).is_empty());
+    }
+}
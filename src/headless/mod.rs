@@ -0,0 +1,64 @@
+//! 无界面运行时使用的 `UpdateUi` 实现
+
+use std::{
+    io::{self, Write},
+    path::Path,
+    process,
+    time::Duration,
+};
+
+use crate::{
+    ipc::Event,
+    update::{FailReason, UpdatePhase, UpdateUi},
+};
+
+/// 以换行分隔的 JSON 向标准输出打印更新事件，事件格式与 IPC 通道一致
+///
+/// 更新成功时以 0 退出，失败时以 [`FailReason::exit_code`] 对应的退出码退出
+pub struct JsonLinesUi;
+
+impl JsonLinesUi {
+    fn print(&self, event: &Event) {
+        let Ok(line) = serde_json::to_string(event) else {
+            return;
+        };
+        let mut stdout = io::stdout().lock();
+        let _ = writeln!(stdout, "{line}");
+        let _ = stdout.flush();
+    }
+}
+
+impl UpdateUi for JsonLinesUi {
+    fn on_progress(&self, progress: f64) {
+        self.print(&Event::Progress { progress });
+    }
+
+    fn on_bytes(&self, done: u64, total: u64, eta: Option<Duration>) {
+        self.print(&Event::bytes(done, total, eta));
+    }
+
+    fn on_phase(&self, phase: UpdatePhase) {
+        self.print(&Event::Phase { phase });
+    }
+
+    fn on_file(&self, current: usize, total: usize, path: &Path) {
+        self.print(&Event::File {
+            current,
+            total,
+            path: &path.to_string_lossy(),
+        });
+    }
+
+    fn on_failed_with(&self, reason: FailReason) {
+        self.print(&Event::Failed {
+            reason,
+            exit_code: reason.exit_code(),
+        });
+        process::exit(reason.exit_code());
+    }
+
+    fn on_quit(&self) {
+        self.print(&Event::Quit);
+        process::exit(0);
+    }
+}
@@ -0,0 +1,320 @@
+//! 更新窗口中显示的文字
+//!
+//! 语言依次取环境变量 `update_locale`、配置文件中的 `ui.locale`、系统的 `LC_ALL`、
+//! `LC_MESSAGES`、`LANG`，以及 Windows 和 macOS 的用户语言设置。系统语言不受支持或无法确定时
+//! 使用英文。日志始终使用中文，便于排查问题。
+
+use std::{env, time::Duration};
+
+use crate::update::{FailReason, UpdatePhase};
+
+/// 支持的语言
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Locale {
+    ZhCn,
+    EnUs,
+}
+
+impl Locale {
+    /// 解析 `zh_CN.UTF-8`、`en-US`、`zh` 这样的语言标记，不支持的语言返回 `None`
+    ///
+    /// 中文只接受简体（`zh`、`zh-CN`、`zh-SG`、`zh-Hans`），`zh-TW`、`zh-HK` 等繁体中文在有对应的
+    /// 文字之前不受支持
+    pub fn parse(tag: &str) -> Option<Locale> {
+        let tag = tag
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let mut subtags = tag.split(['_', '-']);
+        match (subtags.next(), subtags.next()) {
+            (Some("zh"), None | Some("cn" | "sg" | "hans")) => Some(Locale::ZhCn),
+            (Some("en"), _) => Some(Locale::EnUs),
+            _ => None,
+        }
+    }
+
+    /// 按环境变量、`configured`（配置文件中的 `ui.locale`）和系统语言确定使用的语言
+    pub fn detect(configured: Option<&str>) -> Locale {
+        let explicit = [
+            env::var("update_locale").ok(),
+            configured.map(str::to_string),
+        ];
+        if let Some(locale) = explicit.iter().flatten().find_map(|tag| Locale::parse(tag)) {
+            return locale;
+        }
+        // 与 POSIX 的优先级一致，空值视为未设置
+        let system = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|tag| !tag.is_empty())
+            .or_else(system_locale);
+        match system {
+            Some(tag) if tag == "C" || tag == "POSIX" => Locale::EnUs,
+            Some(tag) => Locale::parse(&tag).unwrap_or(Locale::EnUs),
+            None => Locale::EnUs,
+        }
+    }
+
+    /// 更新说明等按语言区分的内容使用的标记
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::ZhCn => "zh-CN",
+            Locale::EnUs => "en-US",
+        }
+    }
+
+    pub fn messages(self) -> &'static Messages {
+        match self {
+            Locale::ZhCn => &ZH_CN,
+            Locale::EnUs => &EN_US,
+        }
+    }
+}
+
+/// 一种语言的全部文字，带 `{}` 的项由调用方替换
+pub struct Messages {
+    pub window_title: &'static str,
+    pub demo_window_title: &'static str,
+    pub updating: &'static str,
+    pub completed: &'static str,
+    pub failed: &'static str,
+    pub cancelled: &'static str,
+    pub progress: &'static str,
+    /// 剩余时间，`{}` 为秒数
+    pub eta_seconds: &'static str,
+    /// 剩余时间，`{}` 为分钟数
+    pub eta_minutes: &'static str,
+    pub done: &'static str,
+    pub retry: &'static str,
+    pub cancel: &'static str,
+    pub cancelling: &'static str,
+    pub close: &'static str,
+    pub show_details: &'static str,
+    pub hide_details: &'static str,
+    pub phase: &'static str,
+    pub file: &'static str,
+    /// 已迁移的文件数，两个 `{}` 依次为已完成数和总数
+    pub files_done: &'static str,
+    pub log: &'static str,
+    pub release_notes: &'static str,
+    pub update_available: &'static str,
+    /// 新版本，`{}` 为版本号
+    pub version: &'static str,
+    /// 更新大小，`{}` 为带单位的大小
+    pub size: &'static str,
+    pub install_now: &'static str,
+    pub later: &'static str,
+    /// 各更新阶段的名称
+    pub phases: fn(UpdatePhase) -> &'static str,
+    /// 各失败原因的说明
+    pub reasons: fn(FailReason) -> &'static str,
+}
+
+impl Messages {
+    pub fn phase_name(&self, phase: UpdatePhase) -> &'static str {
+        (self.phases)(phase)
+    }
+
+    pub fn fail_reason(&self, reason: FailReason) -> &'static str {
+        (self.reasons)(reason)
+    }
+
+    /// 剩余时间的文字，一分钟以内按秒显示，否则按分钟向上取整
+    pub fn eta(&self, eta: Duration) -> String {
+        let secs = eta.as_secs_f64().ceil() as u64;
+        if secs < 60 {
+            self.eta_seconds.replace("{}", &secs.max(1).to_string())
+        } else {
+            self.eta_minutes
+                .replace("{}", &secs.div_ceil(60).to_string())
+        }
+    }
+}
+
+static ZH_CN: Messages = Messages {
+    window_title: "更新程序",
+    demo_window_title: "更新程序（演示模式）",
+    updating: "正在更新...",
+    completed: "更新完成",
+    failed: "更新失败",
+    cancelled: "已取消",
+    progress: "进度",
+    eta_seconds: "约剩 {} 秒",
+    eta_minutes: "约剩 {} 分钟",
+    done: "完成",
+    retry: "重试",
+    cancel: "取消",
+    cancelling: "正在取消...",
+    close: "关闭",
+    show_details: "显示详情",
+    hide_details: "隐藏详情",
+    phase: "阶段",
+    file: "文件",
+    files_done: "{} / {} 个文件",
+    log: "日志",
+    release_notes: "更新内容",
+    update_available: "有可用的更新",
+    version: "版本 {}",
+    size: "大小 {}",
+    install_now: "立即安装",
+    later: "稍后",
+    phases: zh_cn_phase,
+    reasons: zh_cn_reason,
+};
+
+static EN_US: Messages = Messages {
+    window_title: "Updater",
+    demo_window_title: "Updater (demo)",
+    updating: "Updating...",
+    completed: "Update complete",
+    failed: "Update failed",
+    cancelled: "Cancelled",
+    progress: "Progress",
+    eta_seconds: "About {} s left",
+    eta_minutes: "About {} min left",
+    done: "Done",
+    retry: "Retry",
+    cancel: "Cancel",
+    cancelling: "Cancelling...",
+    close: "Close",
+    show_details: "Show details",
+    hide_details: "Hide details",
+    phase: "Phase",
+    file: "File",
+    files_done: "{} / {} files",
+    log: "Log",
+    release_notes: "What's new",
+    update_available: "Update available",
+    version: "Version {}",
+    size: "Size {}",
+    install_now: "Install now",
+    later: "Later",
+    phases: en_us_phase,
+    reasons: en_us_reason,
+};
+
+fn zh_cn_phase(phase: UpdatePhase) -> &'static str {
+    match phase {
+        UpdatePhase::Downloading => "下载更新",
+        UpdatePhase::Preparing => "读取更新配置",
+        UpdatePhase::KillingElectron => "结束 electron 进程",
+        UpdatePhase::BackingUp => "备份旧文件",
+        UpdatePhase::Copying => "迁移文件",
+        UpdatePhase::RollingBack => "回滚文件",
+        UpdatePhase::Cleaning => "清理更新文件",
+        UpdatePhase::Relaunching => "重启程序",
+    }
+}
+
+fn zh_cn_reason(reason: FailReason) -> &'static str {
+    match reason {
+        FailReason::RunningState => "运行状态文件无法读写",
+        FailReason::InstallDir => "无法确定安装目录",
+        FailReason::Manifest => "更新配置读取失败",
+        FailReason::Permission => "检查权限或备份失败",
+        FailReason::Copy => "迁移文件失败，已回滚",
+        FailReason::Relaunch => "重启程序失败",
+        FailReason::RolledBack => "上次更新失败，已回滚",
+        FailReason::Integrity => "文件校验失败",
+        FailReason::Config => "配置无效",
+        FailReason::Download => "下载失败",
+        FailReason::Proxy => "代理连接失败",
+        FailReason::Certificate => "证书校验失败",
+        FailReason::Cancelled => "已取消",
+    }
+}
+
+fn en_us_phase(phase: UpdatePhase) -> &'static str {
+    match phase {
+        UpdatePhase::Downloading => "Downloading update",
+        UpdatePhase::Preparing => "Reading manifest",
+        UpdatePhase::KillingElectron => "Stopping electron",
+        UpdatePhase::BackingUp => "Backing up files",
+        UpdatePhase::Copying => "Migrating files",
+        UpdatePhase::RollingBack => "Rolling back",
+        UpdatePhase::Cleaning => "Cleaning up",
+        UpdatePhase::Relaunching => "Relaunching",
+    }
+}
+
+fn en_us_reason(reason: FailReason) -> &'static str {
+    match reason {
+        FailReason::RunningState => "Cannot access the running status file",
+        FailReason::InstallDir => "Cannot find the install directory",
+        FailReason::Manifest => "Cannot read the update manifest",
+        FailReason::Permission => "Permission check or backup failed",
+        FailReason::Copy => "Copying files failed, rolled back",
+        FailReason::Relaunch => "Could not restart the app",
+        FailReason::RolledBack => "Previous update failed, rolled back",
+        FailReason::Integrity => "Files failed verification",
+        FailReason::Config => "Invalid configuration",
+        FailReason::Download => "Download failed",
+        FailReason::Proxy => "Proxy error",
+        FailReason::Certificate => "Certificate error",
+        FailReason::Cancelled => "Cancelled",
+    }
+}
+
+/// Windows 的用户语言，例如 `zh-CN`
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    crate::win32::user_default_locale_name()
+}
+
+/// macOS 的用户语言，例如 `zh_CN`
+#[cfg(target_os = "macos")]
+fn system_locale() -> Option<String> {
+    let output = std::process::Command::new("defaults")
+        .args(["read", "-g", "AppleLocale"])
+        .output()
+        .ok()?;
+    let tag = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!tag.is_empty()).then_some(tag)
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn system_locale() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_language_tags() {
+        assert_eq!(Locale::parse("zh_CN.UTF-8"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("zh"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("zh-SG"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("zh-Hans-HK"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("zh-TW"), None);
+        assert_eq!(Locale::parse("zh_HK.UTF-8"), None);
+        assert_eq!(Locale::parse("zh-Hant"), None);
+        assert_eq!(Locale::parse("en_US.UTF-8@euro"), Some(Locale::EnUs));
+        assert_eq!(Locale::parse("EN"), Some(Locale::EnUs));
+        assert_eq!(Locale::parse("de_DE"), None);
+        assert_eq!(Locale::parse(""), None);
+    }
+
+    #[test]
+    fn names_every_phase_and_reason() {
+        for locale in [Locale::ZhCn, Locale::EnUs] {
+            let messages = locale.messages();
+            assert!(!messages.phase_name(UpdatePhase::Relaunching).is_empty());
+            assert!(!messages.fail_reason(FailReason::Cancelled).is_empty());
+        }
+        assert_ne!(
+            Locale::ZhCn.messages().phase_name(UpdatePhase::Copying),
+            Locale::EnUs.messages().phase_name(UpdatePhase::Copying)
+        );
+    }
+
+    #[test]
+    fn formats_eta() {
+        let messages = Locale::EnUs.messages();
+        assert_eq!(messages.eta(Duration::from_millis(200)), "About 1 s left");
+        assert_eq!(messages.eta(Duration::from_secs(59)), "About 59 s left");
+        assert_eq!(messages.eta(Duration::from_secs(61)), "About 2 min left");
+    }
+}
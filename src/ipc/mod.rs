@@ -0,0 +1,270 @@
+//! 与父进程（electron）之间的 IPC 通道
+//!
+//! 通过环境变量 `ipc_socket_path`（Unix 域套接字路径）或 `ipc_fd`（继承的文件描述符）启用。
+//! 更新程序以换行分隔的 JSON 推送事件，例如：
+//!
+//! ```text
+//! {"event":"phase","phase":"copying"}
+//! {"event":"file","current":1,"total":2,"path":"/opt/app/resources/app.asar"}
+//! {"event":"progress","progress":0.5}
+//! {"event":"bytes","done":78643200,"total":157286400,"eta_ms":12000}
+//! {"event":"log","level":"info","message":"迁移文件"}
+//! {"event":"quit"}
+//! ```
+//!
+//! 并按行接收命令，目前支持 `{"command":"cancel"}`。
+
+use std::{
+    env,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    logging::{self, ListenerId, Log, LogLevel, Logger},
+    update::{request_cancel, FailReason, UpdatePhase, UpdateSummary, UpdateUi},
+};
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Progress {
+        progress: f64,
+    },
+    Bytes {
+        done: u64,
+        total: u64,
+        /// 剩余时间的估计（毫秒），还无法估计时为空
+        eta_ms: Option<u64>,
+    },
+    Phase {
+        phase: UpdatePhase,
+    },
+    File {
+        current: usize,
+        total: usize,
+        path: &'a str,
+    },
+    Log {
+        level: LogLevel,
+        message: &'a str,
+    },
+    Failed {
+        reason: FailReason,
+        exit_code: i32,
+    },
+    Quit,
+}
+
+impl Event<'_> {
+    pub fn bytes(done: u64, total: u64, eta: Option<Duration>) -> Self {
+        Event::Bytes {
+            done,
+            total,
+            eta_ms: eta.map(|eta| eta.as_millis() as u64),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    Cancel,
+}
+
+#[derive(Clone)]
+pub struct Channel {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl Channel {
+    /// 使用给定的读写端建立通道，并在后台线程中处理收到的命令
+    pub fn new<R, W>(reader: R, writer: W) -> Self
+    where
+        R: Read + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        std::thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Command>(&line) {
+                    Ok(Command::Cancel) => {
+                        Log::info("收到取消更新命令");
+                        request_cancel();
+                    }
+                    Err(e) => {
+                        Log::warn(format!("无法识别的IPC命令: {line} {e}").as_str());
+                    }
+                }
+            }
+        });
+        Self {
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    /// 发送一条事件，写入失败时静默忽略（日志会被转发到这里，不能在此记录日志）
+    pub fn send(&self, event: &Event) {
+        let Ok(mut line) = serde_json::to_string(event) else {
+            return;
+        };
+        line.push('\n');
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writer.write_all(line.as_bytes());
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// 进程中唯一的通道，重试时再次运行任务也使用同一个连接和读取线程
+static CHANNEL: OnceLock<Option<Channel>> = OnceLock::new();
+
+/// 根据环境变量连接父进程，未配置或连接失败时返回 `None`；只在第一次调用时连接
+pub fn connect_from_env() -> Option<Channel> {
+    CHANNEL
+        .get_or_init(|| {
+            let socket_path = env::var("ipc_socket_path").ok();
+            let fd = env::var("ipc_fd").ok();
+            if socket_path.is_none() && fd.is_none() {
+                return None;
+            }
+            let channel = connect(socket_path, fd);
+            if channel.is_some() {
+                Log::info("已连接IPC通道");
+            }
+            channel
+        })
+        .clone()
+}
+
+#[cfg(unix)]
+fn connect(socket_path: Option<String>, fd: Option<String>) -> Option<Channel> {
+    use std::{fs::File, os::fd::FromRawFd, os::unix::net::UnixStream};
+
+    if let Some(path) = socket_path {
+        let stream = match UnixStream::connect(&path) {
+            Ok(stream) => stream,
+            Err(e) => {
+                Log::error(format!("连接IPC套接字失败: {path}").as_str());
+                Log::error(e.to_string().as_str());
+                return None;
+            }
+        };
+        return match stream.try_clone() {
+            Ok(reader) => Some(Channel::new(reader, stream)),
+            Err(e) => {
+                Log::error("复制IPC套接字失败");
+                Log::error(e.to_string().as_str());
+                None
+            }
+        };
+    }
+
+    let fd = match fd.map(|fd| fd.parse::<i32>()) {
+        Some(Ok(fd)) if fd > 2 => fd,
+        _ => {
+            Log::error("ipc_fd 解析失败");
+            return None;
+        }
+    };
+    // SAFETY: ipc_fd 由父进程在启动更新程序时传入并保持打开；这里只在第一次连接时执行一次，
+    // 得到的通道保存在 `CHANNEL` 中直到进程退出，更新程序中没有其他地方持有或关闭它
+    let file = unsafe { File::from_raw_fd(fd) };
+    match file.try_clone() {
+        Ok(reader) => Some(Channel::new(reader, file)),
+        Err(e) => {
+            Log::error("复制IPC文件描述符失败");
+            Log::error(e.to_string().as_str());
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn connect(_socket_path: Option<String>, _fd: Option<String>) -> Option<Channel> {
+    Log::warn("当前平台不支持IPC通道，已忽略 ipc_socket_path/ipc_fd");
+    None
+}
+
+/// 把更新事件同时转发给父进程和内部的 `UpdateUi`
+pub struct IpcUi<U> {
+    inner: U,
+    channel: Channel,
+    listener: ListenerId,
+}
+
+impl<U: UpdateUi> IpcUi<U> {
+    pub fn new(inner: U, channel: Channel) -> Self {
+        let log_channel = channel.clone();
+        let listener = logging::add_listener(Box::new(move |level, message| {
+            log_channel.send(&Event::Log { level, message });
+        }));
+        Self {
+            inner,
+            channel,
+            listener,
+        }
+    }
+}
+
+impl<U> Drop for IpcUi<U> {
+    fn drop(&mut self) {
+        logging::remove_listener(self.listener);
+    }
+}
+
+impl<U: UpdateUi> UpdateUi for IpcUi<U> {
+    fn on_progress(&self, progress: f64) {
+        self.channel.send(&Event::Progress { progress });
+        self.inner.on_progress(progress);
+    }
+
+    fn on_bytes(&self, done: u64, total: u64, eta: Option<Duration>) {
+        self.channel.send(&Event::bytes(done, total, eta));
+        self.inner.on_bytes(done, total, eta);
+    }
+
+    fn on_phase(&self, phase: UpdatePhase) {
+        self.channel.send(&Event::Phase { phase });
+        self.inner.on_phase(phase);
+    }
+
+    fn on_file(&self, current: usize, total: usize, path: &Path) {
+        self.channel.send(&Event::File {
+            current,
+            total,
+            path: &path.to_string_lossy(),
+        });
+        self.inner.on_file(current, total, path);
+    }
+
+    fn on_release_notes(&self, markdown: &str) {
+        self.inner.on_release_notes(markdown);
+    }
+
+    fn confirm(&self, summary: &UpdateSummary) -> bool {
+        self.inner.confirm(summary)
+    }
+
+    fn on_failed_with(&self, reason: FailReason) {
+        self.channel.send(&Event::Failed {
+            reason,
+            exit_code: reason.exit_code(),
+        });
+        self.inner.on_failed_with(reason);
+    }
+
+    fn on_quit(&self) {
+        self.channel.send(&Event::Quit);
+        self.inner.on_quit();
+    }
+}
@@ -1,7 +1,21 @@
+pub mod cli;
+pub mod config;
+pub mod headless;
+pub mod i18n;
+pub mod ipc;
 pub mod logging;
 pub mod update;
 
+#[cfg(feature = "tui")]
+pub mod tui;
+
 #[cfg(feature = "gpui")]
 pub mod ui;
 
-pub use update::{run_task, sysinfo, RunningConfig, RunningState, UpdateUi};
+#[cfg(windows)]
+mod win32;
+
+pub use update::{
+    request_cancel, run_task, run_task_with, sysinfo, FailReason, RunningConfig, RunningState,
+    TaskOptions, UpdatePhase, UpdateUi,
+};
@@ -1,46 +1,58 @@
-use std::{fs::create_dir, path::Path};
+use std::{fs::create_dir_all, sync::Once};
 
-use super::Logger;
+use super::{forward, log_dir, LogLevel, Logger};
 use log::{debug, error, info, warn};
 
 pub struct Log {}
 
+/// 重试和改为无界面运行时会再次调用 `setup_logging`，日志只配置一次
+static SETUP: Once = Once::new();
+
 impl Logger for Log {
     fn setup_logging() {
-        if !Path::new("log").exists() {
-            if let Err(e) = create_dir("log") {
-                eprintln!("create log dir failed: {e}");
-                return;
-            }
-        };
-        let base_config = fern::Dispatch::new().level(log::LevelFilter::Debug);
-        let file_config = fern::Dispatch::new()
-            .format(|out, message, record| {
-                out.finish(format_args!(
-                    "{}[{}][{}] {}",
-                    chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S]"),
-                    record.target(),
-                    record.level(),
-                    message
-                ))
-            })
-            .chain(fern::DateBased::new("log/log.", "%Y-%m-%d"));
-        if let Err(e) = base_config.chain(file_config).apply() {
-            eprintln!("apply log config failed: {e}");
-        }
+        SETUP.call_once(apply_config);
     }
     fn info(info: &str) {
         info!("{info}");
+        forward(LogLevel::Info, info);
     }
     fn debug(debug: &str) {
         debug!("{debug}");
+        forward(LogLevel::Debug, debug);
     }
 
     fn warn(warn: &str) {
         warn!("{warn}");
+        forward(LogLevel::Warn, warn);
     }
 
     fn error(error: &str) {
         error!("{error}");
+        forward(LogLevel::Error, error);
+    }
+}
+
+fn apply_config() {
+    let dir = log_dir();
+    if !dir.exists() {
+        if let Err(e) = create_dir_all(&dir) {
+            eprintln!("create log dir failed: {e}");
+            return;
+        }
+    };
+    let base_config = fern::Dispatch::new().level(log::LevelFilter::Debug);
+    let file_config = fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "{}[{}][{}] {}",
+                chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S]"),
+                record.target(),
+                record.level(),
+                message
+            ))
+        })
+        .chain(fern::DateBased::new(dir.join("log."), "%Y-%m-%d"));
+    if let Err(e) = base_config.chain(file_config).apply() {
+        eprintln!("apply log config failed: {e}");
     }
 }
@@ -1,9 +1,100 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use serde_derive::Serialize;
+
 pub trait Logger {
     fn setup_logging() {}
-    fn info(_info: &str) {}
-    fn debug(_debug: &str) {}
-    fn warn(_warn: &str) {}
-    fn error(_error: &str) {}
+    fn info(info: &str) {
+        forward(LogLevel::Info, info);
+    }
+    fn debug(debug: &str) {
+        forward(LogLevel::Debug, debug);
+    }
+    fn warn(warn: &str) {
+        forward(LogLevel::Warn, warn);
+    }
+    fn error(error: &str) {
+        forward(LogLevel::Error, error);
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+pub type LogListener = Box<dyn Fn(LogLevel, &str) + Send + Sync>;
+
+/// 日志监听者的编号，用于取消监听
+pub type ListenerId = usize;
+
+/// 保存的监听者，转发时复制出来在锁外调用
+type SharedListener = Arc<dyn Fn(LogLevel, &str) + Send + Sync>;
+
+static LISTENERS: Mutex<Vec<(ListenerId, SharedListener)>> = Mutex::new(Vec::new());
+static NEXT_LISTENER: AtomicUsize = AtomicUsize::new(0);
+static LOG_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// 设置日志目录，需要在 `setup_logging` 之前调用，默认为工作目录下的 `log`
+pub fn set_log_dir(dir: PathBuf) {
+    if let Ok(mut current) = LOG_DIR.lock() {
+        *current = Some(dir);
+    }
+}
+
+/// 当前的日志目录
+pub fn log_dir() -> PathBuf {
+    LOG_DIR
+        .lock()
+        .ok()
+        .and_then(|dir| dir.clone())
+        .unwrap_or_else(|| PathBuf::from("log"))
+}
+
+/// 添加日志监听者，之后 info 及以上级别的日志都会同时转发给它，返回的编号用于 `remove_listener`
+///
+/// 监听者在锁外调用，写入阻塞（例如父进程不再读取 IPC 通道）时不会影响添加和取消其他监听者；
+/// 监听者内部不能再调用 `Log`，否则会无限递归
+pub fn add_listener(listener: LogListener) -> ListenerId {
+    let id = NEXT_LISTENER.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut listeners) = LISTENERS.lock() {
+        listeners.push((id, Arc::from(listener)));
+    }
+    id
+}
+
+/// 取消 `add_listener` 添加的监听者
+pub fn remove_listener(id: ListenerId) {
+    if let Ok(mut listeners) = LISTENERS.lock() {
+        listeners.retain(|(current, _)| *current != id);
+    }
+}
+
+pub(crate) fn forward(level: LogLevel, message: &str) {
+    // debug 日志（如完整的更新配置）只写入日志文件，不发送给父进程和更新窗口
+    if level == LogLevel::Debug {
+        return;
+    }
+    let listeners: Vec<_> = match LISTENERS.lock() {
+        Ok(listeners) => listeners
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect(),
+        Err(_) => return,
+    };
+    for listener in listeners.iter() {
+        listener(level, message);
+    }
 }
 
 #[cfg(feature = "debug")]
@@ -17,3 +108,44 @@ pub struct Log {}
 
 #[cfg(not(feature = "debug"))]
 impl Logger for Log {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn forwards_info_and_above() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let id = add_listener(Box::new(move |level, message| {
+            if message.starts_with("forwards_info_and_above") {
+                let _ = sender.lock().unwrap().send(level);
+            }
+        }));
+        for level in [LogLevel::Debug, LogLevel::Info, LogLevel::Error] {
+            forward(level, "forwards_info_and_above");
+        }
+        remove_listener(id);
+        forward(LogLevel::Warn, "forwards_info_and_above");
+        let levels: Vec<LogLevel> = receiver.try_iter().collect();
+        assert_eq!(levels, [LogLevel::Info, LogLevel::Error]);
+    }
+
+    #[test]
+    fn listener_can_remove_listeners() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let id = add_listener(Box::new(move |_, message| {
+            if message == "listener_can_remove_listeners" {
+                // 监听者在锁外调用，这里取消监听不会死锁
+                remove_listener(usize::MAX);
+                let _ = sender.lock().unwrap().send(());
+            }
+        }));
+        forward(LogLevel::Info, "listener_can_remove_listeners");
+        remove_listener(id);
+        assert!(receiver.try_recv().is_ok());
+    }
+}
@@ -2,24 +2,20 @@
 #![windows_subsystem = "windows"]
 
 fn main() {
+    // 没有图形界面时所有输出都在控制台中
+    #[cfg(not(feature = "gpui"))]
+    updater::cli::attach_console();
+    let options = updater::cli::parse();
+
     #[cfg(feature = "demo")]
-    updater::ui::start_demo_ui();
+    updater::ui::start_demo_ui(options);
 
     #[cfg(all(feature = "gpui", not(feature = "demo")))]
-    updater::ui::start_ui();
+    updater::ui::start_ui(options);
+
+    #[cfg(all(feature = "tui", not(feature = "gpui")))]
+    updater::run_task_with(updater::tui::TerminalUi::new(&options.ui), options);
 
-    #[cfg(not(any(feature = "gpui", feature = "demo")))]
-    {
-        struct HeadlessUi;
-        impl updater::UpdateUi for HeadlessUi {
-            fn on_progress(&self, _progress: f64) {}
-            fn on_failed(&self) {
-                std::process::exit(1);
-            }
-            fn on_quit(&self) {
-                std::process::exit(0);
-            }
-        }
-        updater::run_task(HeadlessUi);
-    }
+    #[cfg(not(any(feature = "gpui", feature = "tui")))]
+    updater::run_task_with(updater::headless::JsonLinesUi, options);
 }
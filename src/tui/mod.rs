@@ -0,0 +1,173 @@
+//! 在终端中显示更新进度的 `UpdateUi` 实现，用于 SSH 登录和没有图形界面的自助终端
+//!
+//! 标准错误是终端时在同一行重绘进度条、阶段、当前文件和剩余时间；否则（例如重定向到日志文件）
+//! 每条信息单独输出一行，进度每 10% 输出一次。退出码与 [`crate::headless::JsonLinesUi`] 相同。
+
+use std::{
+    io::{self, IsTerminal, Write},
+    path::Path,
+    process,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    i18n::{Locale, Messages},
+    update::{FailReason, UiOptions, UpdatePhase, UpdateUi},
+};
+
+/// 进度条的宽度（字符）
+const BAR_WIDTH: usize = 24;
+/// 终端中两次重绘之间的最短间隔
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+/// 非终端输出时每隔多少个百分点输出一次进度
+const LINE_STEP: u32 = 10;
+
+pub struct TerminalUi {
+    messages: &'static Messages,
+    tty: bool,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    progress: f64,
+    phase: Option<UpdatePhase>,
+    file: Option<(usize, usize, String)>,
+    eta: Option<Duration>,
+    redrawn_at: Option<Instant>,
+    /// 非终端输出时最近一次输出的进度
+    reported: Option<u32>,
+    /// 已迁移完文件、开始清理，之后退出即表示更新完成
+    cleaning: bool,
+}
+
+impl TerminalUi {
+    pub fn new(options: &UiOptions) -> Self {
+        Self {
+            messages: Locale::detect(options.locale.as_deref()).messages(),
+            tty: io::stderr().is_terminal(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// 终端中清掉进度行再输出 `line`，之后重绘进度行
+    fn println(&self, line: &str) {
+        let mut stderr = io::stderr().lock();
+        if self.tty {
+            let _ = write!(stderr, "\r\x1b[2K");
+        }
+        let _ = writeln!(stderr, "{line}");
+        let _ = stderr.flush();
+    }
+
+    fn update(&self, change: impl FnOnce(&mut State)) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        change(&mut state);
+        if self.tty {
+            let now = Instant::now();
+            let due = state
+                .redrawn_at
+                .is_none_or(|at| now.duration_since(at) >= REDRAW_INTERVAL);
+            if due || state.progress >= 1.0 {
+                state.redrawn_at = Some(now);
+                self.redraw(&state);
+            }
+        } else {
+            let percent = (state.progress * 100.0).floor() as u32;
+            let step = percent / LINE_STEP * LINE_STEP;
+            if step > 0 && state.reported.is_none_or(|reported| step > reported) {
+                state.reported = Some(step);
+                self.println(&format!("{} {step}%", self.messages.progress));
+            }
+        }
+    }
+
+    fn redraw(&self, state: &State) {
+        let filled = ((state.progress * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+        let mut line = format!(
+            "[{}{}] {:>3}%",
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            (state.progress * 100.0).round() as u32
+        );
+        if let Some(phase) = state.phase {
+            line.push_str("  ");
+            line.push_str(self.messages.phase_name(phase));
+        }
+        if let Some((current, total, path)) = &state.file {
+            line.push_str(&format!("  ({current}/{total}) {}", file_name(path)));
+        }
+        if let Some(eta) = state.eta.filter(|eta| !eta.is_zero()) {
+            line.push_str("  ");
+            line.push_str(&self.messages.eta(eta));
+        }
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{line}");
+        let _ = stderr.flush();
+    }
+}
+
+/// 进度行中只显示文件名，完整路径在日志中
+fn file_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
+
+impl UpdateUi for TerminalUi {
+    fn on_progress(&self, progress: f64) {
+        self.update(|state| state.progress = progress.clamp(0.0, 1.0));
+    }
+
+    fn on_bytes(&self, _done: u64, _total: u64, eta: Option<Duration>) {
+        self.update(|state| state.eta = eta);
+    }
+
+    fn on_phase(&self, phase: UpdatePhase) {
+        self.println(&format!("==> {}", self.messages.phase_name(phase)));
+        self.update(|state| {
+            state.phase = Some(phase);
+            state.eta = None;
+            if phase.reports_progress() {
+                state.progress = 0.0;
+                state.reported = None;
+            }
+            state.cleaning |= phase == UpdatePhase::Cleaning;
+            // 上面的输出清掉了进度行，立即重绘
+            state.redrawn_at = None;
+        });
+    }
+
+    fn on_file(&self, current: usize, total: usize, path: &Path) {
+        let path = path.to_string_lossy().into_owned();
+        if !self.tty {
+            self.println(&format!("({current}/{total}) {path}"));
+        }
+        self.update(|state| state.file = Some((current, total, path)));
+    }
+
+    fn on_failed_with(&self, reason: FailReason) {
+        self.println(&format!(
+            "{}: {} ({})",
+            self.messages.failed,
+            self.messages.fail_reason(reason),
+            reason.exit_code()
+        ));
+        process::exit(reason.exit_code());
+    }
+
+    fn on_quit(&self) {
+        // 没有需要安装的更新或选择稍后时也会退出，只在更新完成时提示
+        let completed = self.state.lock().is_ok_and(|state| state.cleaning);
+        if completed {
+            self.println(self.messages.completed);
+        } else if self.tty {
+            let _ = write!(io::stderr(), "\r\x1b[2K");
+        }
+        process::exit(0);
+    }
+}
@@ -1,78 +1,200 @@
+use std::{
+    cell::Cell,
+    env,
+    path::Path,
+    rc::Rc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
 use async_channel::{Receiver, Sender};
 use gpui::*;
 
-use crate::update::{run_task, UpdateUi};
+use crate::i18n::Locale;
+use crate::logging::{self, ListenerId, Log, Logger};
+use crate::update::{
+    request_cancel, retry_task_with, run_task_with, Appearance, FailReason, TaskOptions, UiOptions,
+    UpdatePhase, UpdateSummary, UpdateUi, RUNNING_STATUS_FILE,
+};
 
-use super::view::{UpdateStatus, UpdateView, WINDOW_HEIGHT, WINDOW_WIDTH};
+use super::markdown;
+use super::theme::Theme;
+use super::view::{
+    bind_keys, FileProgress, UpdateStatus, UpdateView, NOTES_HEIGHT, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
 use super::UiMsg;
 
-pub fn start_ui() {
-    if std::env::var("exe_path")
-        .ok()
-        .is_none_or(|path| !std::path::Path::new(&path).is_absolute())
-        && !std::path::Path::new(".running_status").exists()
-    {
+pub fn start_ui(options: TaskOptions) {
+    if options.exe_path.is_none() && !std::path::Path::new(RUNNING_STATUS_FILE).exists() {
         return;
     }
+    Log::setup_logging();
+    if !has_display() {
+        Log::warn("没有设置 DISPLAY 或 WAYLAND_DISPLAY，改为无界面运行");
+        return run_without_window(options);
+    }
+    let messages = Locale::detect(options.ui.locale.as_deref()).messages();
     let app = Application::new();
+    let fallback = options.clone();
+    let window_failed = Rc::new(Cell::new(false));
 
+    let failed = window_failed.clone();
     app.run(move |cx| {
+        bind_keys(cx);
         let (tx, rx) = async_channel::unbounded::<UiMsg>();
-        let bounds = Bounds::centered(None, size(px(WINDOW_WIDTH), px(WINDOW_HEIGHT)), cx);
-        let window_options = WindowOptions {
-            window_bounds: Some(WindowBounds::Windowed(bounds)),
-            is_resizable: false,
-            window_min_size: Some(size(px(WINDOW_WIDTH), px(WINDOW_HEIGHT))),
-            ..Default::default()
-        };
+        let window_options = window_options(&options.ui, cx);
 
-        let _window_handle = cx
-            .open_window(window_options, move |window, cx| {
-                window.set_window_title("更新程序");
-                let view = cx.new(|_cx| UpdateView {
-                    progress: 0.0,
-                    status: UpdateStatus::Downloading,
-                    retry_tx: tx.clone(),
-                });
+        let opened = cx.open_window(window_options, move |window, cx| {
+            let title = window_title(&options.ui, messages.window_title);
+            window.set_window_title(&title);
+            let view = cx.new(|cx| {
+                let theme = theme(&options.ui, window, cx);
+                UpdateView::new(title, tx.clone(), messages, theme, cx)
+            });
 
-                start_event_loop(view.clone(), rx.clone(), tx.clone(), cx);
+            let runner = {
+                let tx = tx.clone();
+                let options = options.clone();
+                thread::spawn(move || run_task_with(GpuiUi::new(tx), options))
+            };
 
-                std::thread::spawn(move || run_task(GpuiUi { tx }));
+            start_event_loop(
+                view.clone(),
+                window.window_handle(),
+                rx.clone(),
+                tx.clone(),
+                options.clone(),
+                runner,
+                cx,
+            );
 
-                view
-            })
-            .expect("Failed to open window");
+            view
+        });
+        // 打开窗口失败时还没有开始更新，退出 gpui 后再执行同一个任务
+        if let Err(e) = opened {
+            Log::warn(format!("打开更新窗口失败，改为无界面运行: {e}").as_str());
+            failed.set(true);
+            // 事件循环开始时会清除之前的退出请求，等循环运行后再退出
+            cx.spawn(async move |cx| cx.update(|cx| cx.quit())).detach();
+        }
     });
+    if window_failed.get() {
+        run_without_window(fallback);
+    }
+}
+
+/// Windows 和 macOS 总是可以打开窗口，其他平台需要 X11 或 Wayland 显示服务器
+fn has_display() -> bool {
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        return true;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|name| env::var_os(name).is_some_and(|value| !value.is_empty()))
+}
+
+/// 没有窗口时执行同一个任务，启用 `tui` 特性时在终端显示进度，否则输出 JSON 行
+fn run_without_window(options: TaskOptions) {
+    crate::cli::attach_console();
+    #[cfg(feature = "tui")]
+    run_task_with(crate::tui::TerminalUi::new(&options.ui), options);
+    #[cfg(not(feature = "tui"))]
+    run_task_with(crate::headless::JsonLinesUi, options);
 }
 
 #[cfg(feature = "demo")]
-pub fn start_demo_ui() {
+pub fn start_demo_ui(options: TaskOptions) {
+    let messages = Locale::detect(options.ui.locale.as_deref()).messages();
     let app = Application::new();
 
     app.run(move |cx| {
-        let bounds = Bounds::centered(None, size(px(WINDOW_WIDTH), px(WINDOW_HEIGHT)), cx);
-        let window_options = WindowOptions {
-            window_bounds: Some(WindowBounds::Windowed(bounds)),
-            is_resizable: false,
-            window_min_size: Some(size(px(WINDOW_WIDTH), px(WINDOW_HEIGHT))),
-            ..Default::default()
-        };
+        bind_keys(cx);
+        let window_options = window_options(&options.ui, cx);
         let (tx, _rx) = async_channel::unbounded::<UiMsg>();
         let _window_handle = cx
             .open_window(window_options, move |window, cx| {
-                window.set_window_title("更新程序（演示模式）");
-                cx.new(|_cx| UpdateView {
-                    progress: 0.6,
-                    status: UpdateStatus::Downloading,
-                    retry_tx: tx.clone(),
+                let title = window_title(&options.ui, messages.demo_window_title);
+                window.set_window_title(&title);
+                grow_window(window, NOTES_HEIGHT);
+                cx.new(|cx| {
+                    let theme = theme(&options.ui, window, cx);
+                    let mut view = UpdateView::new(title, tx.clone(), messages, theme, cx);
+                    view.progress = 0.6;
+                    view.eta = Some(Duration::from_secs(30));
+                    view.phase = Some(UpdatePhase::Copying);
+                    view.file = Some(FileProgress {
+                        current: 42,
+                        total: 70,
+                        path: "resources/app.asar".to_string(),
+                    });
+                    view.push_log(logging::LogLevel::Info, "读取更新配置成功".to_string());
+                    view.push_log(
+                        logging::LogLevel::Info,
+                        "迁移的目标文件 resources/app.asar".to_string(),
+                    );
+                    view.notes = Some(markdown::parse(
+                        "## 1.2.0\n\n- 新增深色模式\n- 修复启动时偶尔白屏的问题",
+                    ));
+                    view
                 })
             })
             .expect("Failed to open window");
     });
 }
 
-fn start_event_loop(view: Entity<UpdateView>, rx: Receiver<UiMsg>, tx: Sender<UiMsg>, cx: &App) {
+fn window_options(ui: &UiOptions, cx: &App) -> WindowOptions {
+    let size = size(px(ui.width.unwrap_or(WINDOW_WIDTH)), px(ui.height.unwrap_or(WINDOW_HEIGHT)));
+    WindowOptions {
+        window_bounds: Some(WindowBounds::Windowed(Bounds::centered(None, size, cx))),
+        is_resizable: false,
+        window_min_size: Some(size),
+        ..Default::default()
+    }
+}
+
+/// 配置的标题，没有时为“{app_name} {default}”或 `default`
+fn window_title(ui: &UiOptions, default: &str) -> String {
+    match (&ui.title, &ui.app_name) {
+        (Some(title), _) => title.clone(),
+        (None, Some(app_name)) => format!("{app_name} {default}"),
+        (None, None) => default.to_string(),
+    }
+}
+
+/// 读取配色，跟随系统时在系统切换明暗模式后重新绘制
+fn theme(ui: &UiOptions, window: &mut Window, cx: &mut Context<UpdateView>) -> Theme {
+    if ui.appearance == Appearance::System {
+        cx.observe_window_appearance(window, |_, _, cx| cx.notify())
+            .detach();
+    }
+    Theme::from_options(ui)
+}
+
+/// 窗口不可调整大小，显示更多内容时由程序增高窗口
+pub(crate) fn grow_window(window: &mut Window, height: f32) {
+    let mut size = window.viewport_size();
+    size.height += px(height);
+    window.resize(size);
+}
+
+/// 更新完成后显示完成状态的时间
+const COMPLETED_DELAY: Duration = Duration::from_millis(1500);
+
+fn start_event_loop(
+    view: Entity<UpdateView>,
+    window: AnyWindowHandle,
+    rx: Receiver<UiMsg>,
+    tx: Sender<UiMsg>,
+    options: TaskOptions,
+    runner: JoinHandle<()>,
+    cx: &App,
+) {
     cx.spawn(async move |cx| {
+        // 等待用户选择时更新线程阻塞在这里
+        let mut consent: Option<Sender<bool>> = None;
+        let mut runner = Some(runner);
+        // 迁移完文件后才会进入清理阶段，之后更新线程结束即表示更新完成
+        let mut cleaning = false;
         while let Ok(msg) = rx.recv().await {
             match msg {
                 UiMsg::Progress(progress) => {
@@ -80,32 +202,147 @@ fn start_event_loop(view: Entity<UpdateView>, rx: Receiver<UiMsg>, tx: Sender<Ui
                     view.update(cx, |view, cx| {
                         if view.status == UpdateStatus::Downloading {
                             view.progress = progress;
-                            if progress >= 1.0 {
-                                view.status = UpdateStatus::Completed;
-                            }
                             cx.notify();
                         }
                     })
                     .ok();
                 }
-                UiMsg::Failed => {
+                UiMsg::Phase(phase) => {
+                    cleaning |= phase == UpdatePhase::Cleaning;
+                    view.update(cx, |view, cx| {
+                        view.phase = Some(phase);
+                        if phase.reports_progress() {
+                            view.progress = 0.0;
+                            view.eta = None;
+                        }
+                        cx.notify();
+                    })
+                    .ok();
+                }
+                UiMsg::Eta(eta) => {
+                    view.update(cx, |view, cx| {
+                        if view.status == UpdateStatus::Downloading {
+                            view.eta = eta;
+                            cx.notify();
+                        }
+                    })
+                    .ok();
+                }
+                UiMsg::File {
+                    current,
+                    total,
+                    path,
+                } => {
+                    view.update(cx, |view, cx| {
+                        view.file = Some(FileProgress {
+                            current,
+                            total,
+                            path,
+                        });
+                        cx.notify();
+                    })
+                    .ok();
+                }
+                UiMsg::Log(level, message) => {
+                    view.update(cx, |view, cx| {
+                        view.push_log(level, message);
+                        if view.details_open {
+                            cx.notify();
+                        }
+                    })
+                    .ok();
+                }
+                UiMsg::ReleaseNotes(markdown) => show_notes(&view, window, &markdown, cx),
+                UiMsg::Prompt { summary, reply } => {
+                    consent = Some(reply);
+                    if let Some(markdown) = &summary.release_notes {
+                        show_notes(&view, window, markdown, cx);
+                    }
+                    view.update(cx, |view, cx| {
+                        view.status = UpdateStatus::Prompt;
+                        view.summary = Some(summary);
+                        cx.notify();
+                    })
+                    .ok();
+                }
+                UiMsg::Consent(install) => {
+                    if let Some(reply) = consent.take() {
+                        let _ = reply.try_send(install);
+                    }
+                    view.update(cx, |view, cx| {
+                        view.status = UpdateStatus::Downloading;
+                        view.summary = None;
+                        cx.notify();
+                    })
+                    .ok();
+                }
+                UiMsg::Failed(reason) => {
                     view.update(cx, |view, cx| {
                         view.status = UpdateStatus::Failed;
+                        view.fail_reason = Some(reason);
+                        cx.notify();
+                    })
+                    .ok();
+                }
+                UiMsg::Cancelled => {
+                    view.update(cx, |view, cx| {
+                        view.status = UpdateStatus::Cancelled;
+                        view.cancelling = false;
+                        cx.notify();
+                    })
+                    .ok();
+                }
+                UiMsg::Cancel => {
+                    request_cancel();
+                    view.update(cx, |view, cx| {
+                        view.cancelling = true;
                         cx.notify();
                     })
                     .ok();
                 }
                 UiMsg::Retry => {
+                    // 只有失败后才能重试，忽略重复的点击
+                    let failed = view
+                        .update(cx, |view, _| view.status == UpdateStatus::Failed)
+                        .unwrap_or(false);
+                    if !failed {
+                        continue;
+                    }
+                    cleaning = false;
                     view.update(cx, |view, cx| {
+                        view.fail_reason = None;
                         view.progress = 0.0;
+                        view.eta = None;
+                        view.phase = None;
+                        view.file = None;
+                        view.cancelling = false;
                         view.status = UpdateStatus::Downloading;
                         cx.notify();
                     })
                     .ok();
+                    // 等上一次的更新线程完全退出后再开始，保证同时只有一个更新线程
+                    let previous = runner.take();
                     let retry_tx = tx.clone();
-                    std::thread::spawn(move || run_task(GpuiUi { tx: retry_tx }));
+                    let options = options.clone();
+                    runner = Some(thread::spawn(move || {
+                        if let Some(previous) = previous {
+                            let _ = previous.join();
+                        }
+                        retry_task_with(GpuiUi::new(retry_tx), options);
+                    }));
                 }
-                UiMsg::Quit => std::process::exit(0),
+                UiMsg::Finished if cleaning => {
+                    view.update(cx, |view, cx| {
+                        view.status = UpdateStatus::Completed;
+                        view.progress = 1.0;
+                        view.eta = None;
+                        cx.notify();
+                    })
+                    .ok();
+                    cx.background_executor().timer(COMPLETED_DELAY).await;
+                    std::process::exit(0);
+                }
+                UiMsg::Finished | UiMsg::Quit => std::process::exit(0),
             }
         }
 
@@ -114,8 +351,48 @@ fn start_event_loop(view: Entity<UpdateView>, rx: Receiver<UiMsg>, tx: Sender<Ui
     .detach();
 }
 
+/// 显示更新说明，第一次显示时增高窗口
+fn show_notes(
+    view: &Entity<UpdateView>,
+    window: AnyWindowHandle,
+    markdown: &str,
+    cx: &mut AsyncApp,
+) {
+    let first = view
+        .update(cx, |view, cx| {
+            let first = view.notes.is_none();
+            view.notes = Some(markdown::parse(markdown));
+            cx.notify();
+            first
+        })
+        .unwrap_or(false);
+    if first {
+        window
+            .update(cx, |_, window, _| grow_window(window, NOTES_HEIGHT))
+            .ok();
+    }
+}
+
+/// 把更新事件转发给窗口，存在期间同时转发日志
 struct GpuiUi {
     tx: Sender<UiMsg>,
+    listener: ListenerId,
+}
+
+impl GpuiUi {
+    fn new(tx: Sender<UiMsg>) -> Self {
+        let log_tx = tx.clone();
+        let listener = logging::add_listener(Box::new(move |level, message| {
+            let _ = log_tx.try_send(UiMsg::Log(level, message.to_string()));
+        }));
+        Self { tx, listener }
+    }
+}
+
+impl Drop for GpuiUi {
+    fn drop(&mut self) {
+        logging::remove_listener(self.listener);
+    }
 }
 
 impl UpdateUi for GpuiUi {
@@ -123,11 +400,45 @@ impl UpdateUi for GpuiUi {
         let _ = self.tx.try_send(UiMsg::Progress(progress as f32));
     }
 
-    fn on_failed(&self) {
-        let _ = self.tx.try_send(UiMsg::Failed);
+    fn on_phase(&self, phase: UpdatePhase) {
+        let _ = self.tx.try_send(UiMsg::Phase(phase));
+    }
+
+    fn on_bytes(&self, _done: u64, _total: u64, eta: Option<Duration>) {
+        let _ = self.tx.try_send(UiMsg::Eta(eta));
+    }
+
+    fn on_file(&self, current: usize, total: usize, path: &Path) {
+        let _ = self.tx.try_send(UiMsg::File {
+            current,
+            total,
+            path: path.to_string_lossy().into_owned(),
+        });
+    }
+
+    fn on_release_notes(&self, markdown: &str) {
+        let _ = self.tx.try_send(UiMsg::ReleaseNotes(markdown.to_string()));
+    }
+
+    fn confirm(&self, summary: &UpdateSummary) -> bool {
+        let (reply, answer) = async_channel::bounded(1);
+        let prompt = UiMsg::Prompt {
+            summary: summary.clone(),
+            reply,
+        };
+        // 窗口已关闭时不安装
+        self.tx.try_send(prompt).is_ok() && answer.recv_blocking().unwrap_or(false)
+    }
+
+    fn on_failed_with(&self, reason: FailReason) {
+        let _ = self.tx.try_send(if reason == FailReason::Cancelled {
+            UiMsg::Cancelled
+        } else {
+            UiMsg::Failed(reason)
+        });
     }
 
     fn on_quit(&self) {
-        let _ = self.tx.try_send(UiMsg::Quit);
+        let _ = self.tx.try_send(UiMsg::Finished);
     }
 }
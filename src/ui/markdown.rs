@@ -0,0 +1,115 @@
+//! 更新说明使用的简化 Markdown
+//!
+//! 只区分标题、列表项、代码块和段落，行内的强调、代码和链接去掉标记后按普通文字显示。
+
+pub(crate) enum Block {
+    Heading(String),
+    Bullet(String),
+    Code(String),
+    Paragraph(String),
+}
+
+pub(crate) fn parse(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Some(lines) = code.as_mut() {
+            if trimmed.starts_with("```") {
+                blocks.push(Block::Code(lines.join("\n")));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+        let block = if trimmed.starts_with("```") {
+            code = Some(Vec::new());
+            None
+        } else if trimmed.is_empty() || is_rule(trimmed) {
+            None
+        } else if let Some(text) = heading(trimmed) {
+            Some(Block::Heading(inline(text)))
+        } else if let Some(text) = bullet(trimmed) {
+            Some(Block::Bullet(inline(text)))
+        } else {
+            paragraph.push(trimmed);
+            continue;
+        };
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(inline(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+        blocks.extend(block);
+    }
+    if !paragraph.is_empty() {
+        blocks.push(Block::Paragraph(inline(&paragraph.join(" "))));
+    }
+    if let Some(lines) = code {
+        blocks.push(Block::Code(lines.join("\n")));
+    }
+    blocks
+}
+
+fn is_rule(line: &str) -> bool {
+    line.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|mark| line.chars().all(|c| c == *mark || c == ' '))
+}
+
+fn heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    ((1..=6).contains(&level) && text.starts_with(' ')).then(|| text.trim())
+}
+
+/// `- `、`* `、`+ ` 和 `1. ` 开头的列表项
+fn bullet(line: &str) -> Option<&str> {
+    if let Some(text) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|mark| line.strip_prefix(mark))
+    {
+        return Some(text.trim());
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+        .map(str::trim)
+}
+
+/// 去掉行内的强调和代码标记，链接和图片只保留文字
+fn inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '[' || (c == '!' && rest[1..].starts_with('[')) {
+            let start = if c == '!' { 2 } else { 1 };
+            if let Some((label, after)) = rest[start..].split_once("](") {
+                if let Some((_, tail)) = after.split_once(')') {
+                    out.push_str(&inline(label));
+                    rest = tail;
+                    continue;
+                }
+            }
+        }
+        if !matches!(c, '*' | '_' | '`') || c == '_' && is_word_underscore(&out, rest) {
+            out.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// 单词中间的下划线（如 `update_temp`）不是强调标记
+fn is_word_underscore(before: &str, rest: &str) -> bool {
+    let prev = before.chars().last().is_some_and(char::is_alphanumeric);
+    let next = rest[1..].chars().next().is_some_and(char::is_alphanumeric);
+    prev && next
+}
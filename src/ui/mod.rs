@@ -1,10 +1,48 @@
 mod logic;
+mod markdown;
+mod theme;
 mod view;
 
+use std::time::Duration;
+
+use async_channel::Sender;
+
+use crate::{
+    logging::LogLevel,
+    update::{FailReason, UpdatePhase, UpdateSummary},
+};
+
+#[derive(Clone)]
 pub(crate) enum UiMsg {
     Progress(f32),
-    Failed,
+    Phase(UpdatePhase),
+    /// 剩余时间的估计，还无法估计时为空
+    Eta(Option<Duration>),
+    /// 开始迁移第 `current` 个文件（从 1 开始），共 `total` 个
+    File {
+        current: usize,
+        total: usize,
+        path: String,
+    },
+    Log(LogLevel, String),
+    /// 当前语言的更新说明（Markdown）
+    ReleaseNotes(String),
+    /// 询问用户是否立即安装，用户的选择通过 `reply` 返回给更新线程
+    Prompt {
+        summary: UpdateSummary,
+        reply: Sender<bool>,
+    },
+    /// 用户选择了立即安装（`true`）或稍后（`false`）
+    Consent(bool),
+    Failed(FailReason),
+    /// 更新已取消并回滚
+    Cancelled,
+    /// 用户点击了取消
+    Cancel,
     Retry,
+    /// 更新线程已结束（`on_quit`），更新完成时先显示完成状态再退出
+    Finished,
+    /// 用户关闭了窗口
     Quit,
 }
 
@@ -0,0 +1,140 @@
+//! 更新窗口的配色和品牌信息
+
+use std::{env, path::PathBuf};
+
+use gpui::{rgba, Rgba, Window, WindowAppearance};
+
+use crate::update::{parse_color, Appearance, PaletteOptions, UiOptions};
+
+/// 未配置 `ui.logo` 时在更新程序所在目录下查找的图标
+const DEFAULT_LOGO: &str = "updater-logo.png";
+
+#[derive(Clone, Copy)]
+pub(crate) struct Palette {
+    pub(crate) background: Rgba,
+    pub(crate) card: Rgba,
+    pub(crate) border: Rgba,
+    pub(crate) text: Rgba,
+    pub(crate) text_muted: Rgba,
+    pub(crate) text_hint: Rgba,
+    pub(crate) track: Rgba,
+    pub(crate) accent: Rgba,
+    pub(crate) accent_text: Rgba,
+    pub(crate) success: Rgba,
+    pub(crate) success_text: Rgba,
+    pub(crate) danger: Rgba,
+}
+
+impl Palette {
+    /// 原有的 slate/blue 深色配色
+    fn dark() -> Self {
+        Self {
+            background: rgba(0x0f172aff),
+            card: rgba(0xffffff1a),
+            border: rgba(0xffffff33),
+            text: rgba(0xffffffff),
+            text_muted: rgba(0xffffff99),
+            text_hint: rgba(0xffffff66),
+            track: rgba(0xffffff1a),
+            accent: rgba(0x3b82f6ff),
+            accent_text: rgba(0x60a5faff),
+            success: rgba(0x22c55eff),
+            success_text: rgba(0x4ade80ff),
+            danger: rgba(0xef4444ff),
+        }
+    }
+
+    fn light() -> Self {
+        Self {
+            background: rgba(0xf1f5f9ff),
+            card: rgba(0xffffffff),
+            border: rgba(0xe2e8f0ff),
+            text: rgba(0x0f172aff),
+            text_muted: rgba(0x475569ff),
+            text_hint: rgba(0x64748bff),
+            track: rgba(0xe2e8f0ff),
+            accent: rgba(0x2563ebff),
+            accent_text: rgba(0x2563ebff),
+            success: rgba(0x16a34aff),
+            success_text: rgba(0x16a34aff),
+            danger: rgba(0xdc2626ff),
+        }
+    }
+
+    /// 用配置中的颜色覆盖对应的项，无效的颜色已在检查配置时报告，这里直接忽略
+    fn with_overrides(mut self, options: &PaletteOptions) -> Self {
+        let slots = [
+            &mut self.background,
+            &mut self.card,
+            &mut self.border,
+            &mut self.text,
+            &mut self.text_muted,
+            &mut self.text_hint,
+            &mut self.track,
+            &mut self.accent,
+            &mut self.accent_text,
+            &mut self.success,
+            &mut self.success_text,
+            &mut self.danger,
+        ];
+        for (slot, (_, value)) in slots.into_iter().zip(options.entries()) {
+            if let Some(color) = value.and_then(parse_color) {
+                *slot = rgba(color);
+            }
+        }
+        self
+    }
+
+    /// `danger` 的浅色版本，用作失败图标的背景
+    pub(crate) fn danger_soft(&self) -> Rgba {
+        Rgba {
+            a: 0.2,
+            ..self.danger
+        }
+    }
+}
+
+pub(crate) struct Theme {
+    appearance: Appearance,
+    light: Palette,
+    dark: Palette,
+    /// 显示在窗口中的产品名称
+    pub(crate) app_name: Option<String>,
+    /// 进度环中的图标
+    pub(crate) logo: Option<PathBuf>,
+}
+
+impl Theme {
+    pub(crate) fn from_options(options: &UiOptions) -> Self {
+        let logo = options.logo.clone().or_else(|| {
+            env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_LOGO)))
+                .filter(|path| path.is_file())
+        });
+        Self {
+            appearance: options.appearance,
+            light: Palette::light().with_overrides(&options.light),
+            dark: Palette::dark().with_overrides(&options.dark),
+            app_name: options.app_name.clone(),
+            logo,
+        }
+    }
+
+    /// 按配置（或跟随系统时按窗口当前的外观）选择配色
+    pub(crate) fn palette(&self, window: &Window) -> &Palette {
+        let dark = match self.appearance {
+            Appearance::Dark => true,
+            Appearance::Light => false,
+            Appearance::System => matches!(
+                window.appearance(),
+                WindowAppearance::Dark | WindowAppearance::VibrantDark
+            ),
+        };
+        if dark {
+            &self.dark
+        } else {
+            &self.light
+        }
+    }
+}
@@ -1,225 +1,750 @@
+use std::{
+    collections::VecDeque,
+    f32::consts::{FRAC_PI_2, TAU},
+    time::Duration,
+};
+
 use async_channel::Sender;
-use gpui::*;
+use gpui::{prelude::FluentBuilder, *};
+
+use crate::{
+    i18n::Messages,
+    logging::LogLevel,
+    update::{FailReason, UpdatePhase, UpdateSummary},
+};
 
-use super::UiMsg;
+use super::{
+    logic::grow_window,
+    markdown::Block,
+    theme::{Palette, Theme},
+    UiMsg,
+};
 
 pub(crate) const WINDOW_WIDTH: f32 = 360.0;
 pub(crate) const WINDOW_HEIGHT: f32 = 220.0;
 
+/// 进度环的直径和线宽
+const RING_SIZE: f32 = 48.0;
+const RING_WIDTH: f32 = 4.0;
+/// 不确定进度时转动的圆弧占整圈的比例，以及转一圈的时间
+const SPINNER_SWEEP: f32 = 0.25;
+const SPINNER_PERIOD: Duration = Duration::from_millis(900);
+/// 状态切换时图标和按钮的过渡时间
+const TRANSITION: Duration = Duration::from_millis(300);
+/// 详情中保留的日志行数，以及展开详情时窗口增加的高度
+const LOG_LINES: usize = 6;
+const DETAILS_HEIGHT: f32 = 240.0;
+/// 更新说明区域的最大高度，收到更新说明时窗口增加 `NOTES_HEIGHT`
+const NOTES_MAX_HEIGHT: f32 = 120.0;
+pub(crate) const NOTES_HEIGHT: f32 = 160.0;
+/// 通过窗口标题播报进度的间隔（百分点）
+const ANNOUNCE_STEP: i32 = 10;
+
+actions!(updater, [FocusNext, FocusPrev, Dismiss]);
+
+/// Tab、Shift-Tab 切换按钮，Esc 取消、稍后或关闭；Enter 和空格由选中的按钮处理
+pub(crate) fn bind_keys(cx: &mut App) {
+    cx.bind_keys([
+        KeyBinding::new("tab", FocusNext, Some("UpdateView")),
+        KeyBinding::new("shift-tab", FocusPrev, Some("UpdateView")),
+        KeyBinding::new("escape", Dismiss, Some("UpdateView")),
+    ]);
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub(crate) enum UpdateStatus {
+    /// 等待用户选择立即安装或稍后
+    Prompt,
     Downloading,
     Completed,
     Failed,
     Cancelled,
 }
 
+/// 正在迁移的文件
+pub(crate) struct FileProgress {
+    /// 从 1 开始的序号
+    pub(crate) current: usize,
+    pub(crate) total: usize,
+    pub(crate) path: String,
+}
+
 pub(crate) struct UpdateView {
     pub(crate) progress: f32,
+    /// 剩余时间的估计，还无法估计时为空
+    pub(crate) eta: Option<Duration>,
     pub(crate) status: UpdateStatus,
+    /// 最近一次收到的更新阶段
+    pub(crate) phase: Option<UpdatePhase>,
+    /// 已请求取消，等待回滚完成
+    pub(crate) cancelling: bool,
+    pub(crate) file: Option<FileProgress>,
+    /// 最近的 `LOG_LINES` 条日志
+    pub(crate) log: VecDeque<(LogLevel, String)>,
+    /// 是否展开详情
+    pub(crate) details_open: bool,
+    /// 更新说明，没有时为空
+    pub(crate) notes: Option<Vec<Block>>,
+    /// 询问用户时显示的版本和大小
+    pub(crate) summary: Option<UpdateSummary>,
+    /// 最近一次失败的原因
+    pub(crate) fail_reason: Option<FailReason>,
     pub(crate) retry_tx: Sender<UiMsg>,
+    pub(crate) messages: &'static Messages,
+    pub(crate) theme: Theme,
+    /// 不含状态的窗口标题
+    title: String,
+    /// 最近一次写入窗口标题的状态，屏幕阅读器会播报标题的变化
+    announced: String,
+    focus: FocusHandle,
+    /// 当前状态下的主要按钮，以及询问时的“稍后”按钮
+    primary_focus: FocusHandle,
+    secondary_focus: FocusHandle,
+    details_focus: FocusHandle,
+    /// 已经把焦点移到主要按钮的状态，状态变化后重新移动
+    focused_status: Option<(UpdateStatus, bool)>,
+}
+
+impl UpdateView {
+    pub(crate) fn new(
+        title: String,
+        retry_tx: Sender<UiMsg>,
+        messages: &'static Messages,
+        theme: Theme,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        Self {
+            progress: 0.0,
+            eta: None,
+            status: UpdateStatus::Downloading,
+            phase: None,
+            cancelling: false,
+            file: None,
+            log: VecDeque::with_capacity(LOG_LINES),
+            details_open: false,
+            notes: None,
+            summary: None,
+            fail_reason: None,
+            retry_tx,
+            messages,
+            theme,
+            title,
+            announced: String::new(),
+            focus: cx.focus_handle(),
+            primary_focus: cx.focus_handle().tab_index(2).tab_stop(true),
+            secondary_focus: cx.focus_handle().tab_index(1).tab_stop(true),
+            details_focus: cx.focus_handle().tab_index(0).tab_stop(true),
+            focused_status: None,
+        }
+    }
 }
 
 impl Render for UpdateView {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let progress = self.progress.clamp(0.0, 1.0);
         let percentage = (progress * 100.0).round() as i32;
+        let palette = *self.theme.palette(window);
 
-        // Colors from React version
-        let slate_900 = rgb(0x0f172a);
-        let white_10 = rgba(0xffffff1a);
-        let white_20 = rgba(0xffffff33);
-        let white_60 = rgba(0xffffff99);
-        let blue_400 = rgb(0x60a5fa);
-        let blue_500 = rgb(0x3b82f6);
-        let green_400 = rgb(0x4ade80);
-        let green_500 = rgb(0x22c55e);
-
+        let messages = self.messages;
         let status_text = match self.status {
-            UpdateStatus::Downloading => "正在更新...",
-            UpdateStatus::Completed => "更新完成",
-            UpdateStatus::Failed => "更新失败",
-            UpdateStatus::Cancelled => "已取消",
+            UpdateStatus::Prompt => messages.update_available,
+            UpdateStatus::Downloading if self.cancelling => messages.cancelling,
+            UpdateStatus::Downloading => messages.updating,
+            UpdateStatus::Completed => messages.completed,
+            UpdateStatus::Failed => messages.failed,
+            UpdateStatus::Cancelled => messages.cancelled,
         };
 
         let status_color = match self.status {
-            UpdateStatus::Completed => green_400,
-            _ => blue_400,
+            UpdateStatus::Completed => palette.success_text,
+            _ => palette.accent_text,
         };
 
+        self.announce(status_text, percentage, window);
+        // 状态变化后把焦点移到新的主要按钮上，键盘用户可以直接按 Enter
+        let focus_key = (self.status, self.cancelling);
+        if self.focused_status != Some(focus_key) {
+            self.focused_status = Some(focus_key);
+            let focus = if self.status == UpdateStatus::Downloading && self.cancelling {
+                &self.focus
+            } else {
+                &self.primary_focus
+            };
+            window.focus(focus);
+        }
+
         div()
+            .id("update-view")
+            .track_focus(&self.focus)
+            .key_context("UpdateView")
+            .on_action(cx.listener(|_, _: &FocusNext, window, _| window.focus_next()))
+            .on_action(cx.listener(|_, _: &FocusPrev, window, _| window.focus_prev()))
+            .on_action(cx.listener(|view, _: &Dismiss, _, _| view.dismiss()))
             .size_full()
-            .bg(slate_900)
+            .bg(palette.background)
             .flex()
             .items_center()
             .justify_center()
             .child(
                 div()
-                    .w(px(360.0))
-                    .bg(white_10)
+                    .w_full()
+                    .bg(palette.card)
                     .rounded_xl()
                     .border_1()
-                    .border_color(white_20)
+                    .border_color(palette.border)
                     .overflow_hidden()
                     .flex()
                     .flex_col()
                     .items_center()
                     .p(px(20.0))
                     .child(
-                        // Progress Button Placeholder (Circular Progress)
-                        div().mb(px(8.0)).child(self.render_progress_button(cx)),
+                        // Circular Progress
+                        div()
+                            .mb(px(8.0))
+                            .child(self.render_progress_button(&palette, cx)),
                     )
+                    .when_some(self.theme.app_name.clone(), |this, app_name| {
+                        this.child(
+                            // Product Name
+                            div()
+                                .text_xs()
+                                .text_color(palette.text_muted)
+                                .child(app_name),
+                        )
+                    })
                     .child(
                         // Title
                         div()
                             .text_lg()
                             .font_weight(FontWeight::SEMIBOLD)
-                            .text_color(rgb(0xffffff))
+                            .text_color(palette.text)
                             .mb(px(12.0))
                             .child(status_text),
                     )
-                    .child(
-                        // Progress Section
-                        div()
-                            .w_full()
-                            .mb(px(16.0))
-                            .child(
-                                div()
-                                    .flex()
-                                    .justify_between()
-                                    .items_center()
-                                    .mb(px(8.0))
-                                    .child(div().text_xs().text_color(white_60).child("进度"))
-                                    .child(
-                                        div()
-                                            .text_xs()
-                                            .font_weight(FontWeight::SEMIBOLD)
-                                            .text_color(status_color)
-                                            .child(format!("{}%", percentage)),
-                                    ),
-                            )
-                            .child(
-                                // Progress Bar
-                                div()
-                                    .relative()
-                                    .h(px(8.0))
-                                    .w_full()
-                                    .bg(white_10)
-                                    .rounded_full()
-                                    .overflow_hidden()
-                                    .child(
-                                        div()
-                                            .h_full()
-                                            .bg(if self.status == UpdateStatus::Completed {
-                                                green_500
-                                            } else {
-                                                blue_500
-                                            })
-                                            .rounded_full()
-                                            .w(relative(progress)),
-                                    ),
-                            )
-                            .child(
-                                // Progress Details
-                                div()
-                                    .flex()
-                                    .justify_end()
-                                    .mt(px(8.0))
-                                    .text_xs()
-                                    .text_color(rgba(0xffffff66))
-                                    .child(match self.status {
-                                        UpdateStatus::Downloading => "约剩 30 秒",
-                                        UpdateStatus::Cancelled | UpdateStatus::Failed => "已取消",
-                                        _ => "",
-                                    }),
-                            ),
+                    .when_some(
+                        self.summary
+                            .as_ref()
+                            .filter(|_| self.status == UpdateStatus::Prompt),
+                        |this, summary| this.child(self.render_summary(summary, &palette)),
                     )
+                    .when(self.status != UpdateStatus::Prompt, |this| {
+                        this.child(
+                            // Progress Section
+                            div()
+                                .w_full()
+                                .mb(px(16.0))
+                                .child(
+                                    div()
+                                        .flex()
+                                        .justify_between()
+                                        .items_center()
+                                        .mb(px(8.0))
+                                        .child(
+                                            div()
+                                                .text_xs()
+                                                .text_color(palette.text_muted)
+                                                .child(messages.progress),
+                                        )
+                                        .child(
+                                            div()
+                                                .text_xs()
+                                                .font_weight(FontWeight::SEMIBOLD)
+                                                .text_color(status_color)
+                                                .child(format!("{}%", percentage)),
+                                        ),
+                                )
+                                .child(
+                                    // Progress Bar
+                                    div()
+                                        .relative()
+                                        .h(px(8.0))
+                                        .w_full()
+                                        .bg(palette.track)
+                                        .rounded_full()
+                                        .overflow_hidden()
+                                        .child(
+                                            div()
+                                                .h_full()
+                                                .bg(if self.status == UpdateStatus::Completed {
+                                                    palette.success
+                                                } else {
+                                                    palette.accent
+                                                })
+                                                .rounded_full()
+                                                .w(relative(progress)),
+                                        ),
+                                )
+                                .child(
+                                    // Progress Details
+                                    div()
+                                        .flex()
+                                        .justify_between()
+                                        .mt(px(8.0))
+                                        .text_xs()
+                                        .text_color(palette.text_hint)
+                                        .child(self.render_details_toggle(&palette, cx))
+                                        .child(match self.status {
+                                            UpdateStatus::Downloading => self
+                                                .eta
+                                                .map(|eta| messages.eta(eta))
+                                                .unwrap_or_default(),
+                                            UpdateStatus::Failed => self
+                                                .fail_reason
+                                                .map(|reason| messages.fail_reason(reason))
+                                                .unwrap_or(messages.failed)
+                                                .to_string(),
+                                            UpdateStatus::Cancelled => {
+                                                messages.cancelled.to_string()
+                                            }
+                                            _ => String::new(),
+                                        }),
+                                ),
+                        )
+                    })
+                    .when(self.details_open, |this| this.child(self.render_details(&palette)))
+                    .when_some(self.notes.as_ref(), |this, notes| {
+                        this.child(self.render_notes(notes, &palette))
+                    })
                     .child(
-                        // Action Buttons
-                        self.render_action_button(cx),
+                        // Action Buttons, fade in when the status changes
+                        self.render_action_button(&palette, cx).with_animation(
+                            ("actions", self.status as usize),
+                            Animation::new(TRANSITION).with_easing(ease_out_quint()),
+                            |this, delta| this.opacity(delta),
+                        ),
                     ),
             )
     }
 }
 
 impl UpdateView {
-    fn render_progress_button(&self, _cx: &mut Context<Self>) -> impl IntoElement {
-        let _is_active = self.status == UpdateStatus::Downloading;
+    fn render_progress_button(
+        &self,
+        palette: &Palette,
+        _cx: &mut Context<Self>,
+    ) -> impl IntoElement {
         let is_completed = self.status == UpdateStatus::Completed;
         let is_failed =
             self.status == UpdateStatus::Failed || self.status == UpdateStatus::Cancelled;
 
         let color = if is_completed {
-            rgb(0x22c55e)
+            palette.success
         } else if is_failed {
-            rgb(0xef4444)
+            palette.danger
+        } else {
+            palette.accent_text
+        };
+        let track = palette.track;
+
+        let ring = if self.is_indeterminate() {
+            div()
+                .absolute()
+                .size_full()
+                .with_animation(
+                    "progress-spinner",
+                    Animation::new(SPINNER_PERIOD).repeat(),
+                    move |this, delta| this.child(progress_ring(None, delta, color, track)),
+                )
+                .into_any_element()
         } else {
-            rgb(0x60a5fa)
+            let progress = match self.status {
+                UpdateStatus::Downloading => self.progress.clamp(0.0, 1.0),
+                _ => 1.0,
+            };
+            progress_ring(Some(progress), 0.0, color, track).into_any_element()
         };
 
         div()
             .relative()
-            .size(px(48.0))
+            .size(px(RING_SIZE))
             .flex()
             .items_center()
             .justify_center()
+            .child(ring)
             .child(
-                // Outer ring (simplified as a border for now)
-                div()
-                    .absolute()
-                    .size_full()
-                    .rounded_full()
-                    .border_2()
-                    .border_color(color),
-            )
-            .child(
-                // Inner circle with icon
+                // Inner circle with icon, pops in when the status changes
                 div()
                     .size(px(28.0))
                     .rounded_full()
-                    .bg(if is_failed { rgba(0xef444433) } else { color })
+                    .bg(if is_failed {
+                        palette.danger_soft()
+                    } else {
+                        color
+                    })
+                    .overflow_hidden()
                     .flex()
                     .items_center()
                     .justify_center()
-                    .child(match self.status {
-                        UpdateStatus::Completed => "✓",
-                        UpdateStatus::Failed | UpdateStatus::Cancelled => "✕",
-                        _ => "↓",
-                    }),
+                    .map(|this| match (self.status, &self.theme.logo) {
+                        (UpdateStatus::Completed, _) => this.child("✓"),
+                        (UpdateStatus::Failed | UpdateStatus::Cancelled, _) => this.child("✕"),
+                        (_, Some(logo)) => this.child(img(logo.clone()).size_full()),
+                        (_, None) => this.child("↓"),
+                    })
+                    .with_animation(
+                        ("status-icon", self.status as usize),
+                        Animation::new(TRANSITION).with_easing(ease_out_quint()),
+                        |this, delta| this.opacity(delta).size(px(16.0 + 12.0 * delta)),
+                    ),
             )
     }
 
-    fn render_action_button(&self, _cx: &mut Context<Self>) -> impl IntoElement {
-        let retry_tx = self.retry_tx.clone();
+    /// 没有可用的进度时显示转动的圆弧，例如结束 electron、备份和回滚
+    fn is_indeterminate(&self) -> bool {
+        self.status == UpdateStatus::Downloading
+            && (self.cancelling
+                || !matches!(self.phase, Some(UpdatePhase::Downloading | UpdatePhase::Copying)))
+    }
 
-        match self.status {
-            UpdateStatus::Downloading => div(),
-            UpdateStatus::Completed => div()
-                .w_full()
-                .py(px(6.0))
-                .rounded_lg()
-                .bg(rgb(0x22c55e))
+    fn render_details_toggle(&self, palette: &Palette, cx: &mut Context<Self>) -> Stateful<Div> {
+        let ring = palette.text;
+        div()
+            .id("details-toggle")
+            .track_focus(&self.details_focus)
+            .px(px(2.0))
+            .rounded_sm()
+            .border_1()
+            .border_color(transparent_black())
+            .focus(move |style| style.border_color(ring))
+            .cursor_pointer()
+            .on_click(cx.listener(|view, _, window, cx| {
+                view.details_open = !view.details_open;
+                // 展开时增高窗口，收起时恢复
+                grow_window(
+                    window,
+                    if view.details_open {
+                        DETAILS_HEIGHT
+                    } else {
+                        -DETAILS_HEIGHT
+                    },
+                );
+                cx.notify();
+            }))
+            .child(if self.details_open {
+                format!("{} ▴", self.messages.hide_details)
+            } else {
+                format!("{} ▾", self.messages.show_details)
+            })
+    }
+
+    /// 把状态和进度写入窗口标题，进度按 `ANNOUNCE_STEP` 取整，避免屏幕阅读器频繁播报
+    fn announce(&mut self, status_text: &str, percentage: i32, window: &mut Window) {
+        let announcement = match self.status {
+            UpdateStatus::Downloading if !self.is_indeterminate() => {
+                format!("{status_text} {}%", percentage / ANNOUNCE_STEP * ANNOUNCE_STEP)
+            }
+            UpdateStatus::Failed => match self.fail_reason {
+                Some(reason) => format!("{status_text}: {}", self.messages.fail_reason(reason)),
+                None => status_text.to_string(),
+            },
+            _ => status_text.to_string(),
+        };
+        if announcement != self.announced {
+            window.set_window_title(&format!("{} - {announcement}", self.title));
+            self.announced = announcement;
+        }
+    }
+
+    /// Esc：询问时选择稍后，更新中取消，结束后关闭窗口
+    fn dismiss(&self) {
+        let msg = match self.status {
+            UpdateStatus::Prompt => UiMsg::Consent(false),
+            UpdateStatus::Downloading if self.cancelling => return,
+            UpdateStatus::Downloading => UiMsg::Cancel,
+            UpdateStatus::Completed | UpdateStatus::Failed | UpdateStatus::Cancelled => UiMsg::Quit,
+        };
+        let _ = self.retry_tx.try_send(msg);
+    }
+
+    /// 当前阶段、正在迁移的文件、文件数和最近的日志
+    fn render_details(&self, palette: &Palette) -> Div {
+        let messages = self.messages;
+        let row = |label: &'static str, value: String| {
+            div()
                 .flex()
-                .items_center()
-                .justify_center()
-                .cursor_pointer()
-                .on_mouse_down(MouseButton::Left, move |_, _, _| {
-                    let _ = retry_tx.try_send(UiMsg::Quit);
-                })
-                .child(div().text_sm().text_color(rgb(0xffffff)).child("完成")),
-            _ => div()
+                .gap(px(8.0))
+                .child(div().flex_none().text_color(palette.text_hint).child(label))
+                .child(div().flex_1().min_w_0().truncate().child(value))
+        };
+        let files_done = self.file.as_ref().map(|file| {
+            let done = if self.status == UpdateStatus::Completed {
+                file.total
+            } else {
+                file.current - 1
+            };
+            messages
+                .files_done
+                .replacen("{}", &done.to_string(), 1)
+                .replacen("{}", &file.total.to_string(), 1)
+        });
+
+        div()
+            .w_full()
+            .mb(px(16.0))
+            .p(px(8.0))
+            .rounded_lg()
+            .bg(palette.track)
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .text_xs()
+            .text_color(palette.text_muted)
+            .child(row(
+                messages.phase,
+                self.phase
+                    .map(|phase| messages.phase_name(phase).to_string())
+                    .unwrap_or_default(),
+            ))
+            .child(row(
+                messages.file,
+                self.file
+                    .as_ref()
+                    .map(|file| file.path.clone())
+                    .unwrap_or_default(),
+            ))
+            .when_some(files_done, |this, files_done| this.child(row("", files_done)))
+            .child(div().text_color(palette.text_hint).child(messages.log))
+            .children(self.log.iter().map(|(level, message)| {
+                div()
+                    .truncate()
+                    .text_color(match level {
+                        LogLevel::Error => palette.danger,
+                        LogLevel::Warn => palette.accent_text,
+                        _ => palette.text_muted,
+                    })
+                    .child(message.clone())
+            }))
+    }
+
+    /// 询问用户时代替进度显示新版本和更新大小
+    fn render_summary(&self, summary: &UpdateSummary, palette: &Palette) -> Div {
+        let messages = self.messages;
+        div()
+            .w_full()
+            .mb(px(16.0))
+            .flex()
+            .justify_between()
+            .text_xs()
+            .text_color(palette.text_muted)
+            .child(
+                summary
+                    .version
+                    .as_ref()
+                    .map(|version| messages.version.replace("{}", version))
+                    .unwrap_or_default(),
+            )
+            .child(messages.size.replace("{}", &format_size(summary.size)))
+    }
+
+    /// 可以滚动的更新说明
+    fn render_notes(&self, notes: &[Block], palette: &Palette) -> impl IntoElement {
+        div()
+            .w_full()
+            .mb(px(16.0))
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(palette.text_hint)
+                    .child(self.messages.release_notes),
+            )
+            .child(
+                div()
+                    .id("release-notes")
+                    .max_h(px(NOTES_MAX_HEIGHT))
+                    .overflow_y_scroll()
+                    .p(px(8.0))
+                    .rounded_lg()
+                    .border_1()
+                    .border_color(palette.border)
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .text_xs()
+                    .text_color(palette.text_muted)
+                    .children(notes.iter().map(|block| {
+                        match block {
+                            Block::Heading(text) => div()
+                                .font_weight(FontWeight::SEMIBOLD)
+                                .text_color(palette.text)
+                                .child(text.clone()),
+                            Block::Bullet(text) => div()
+                                .flex()
+                                .gap(px(6.0))
+                                .child(div().flex_none().child("•"))
+                                .child(div().flex_1().child(text.clone())),
+                            Block::Code(text) => div()
+                                .p(px(4.0))
+                                .rounded_md()
+                                .bg(palette.track)
+                                .font_family("monospace")
+                                .child(text.clone()),
+                            Block::Paragraph(text) => div().child(text.clone()),
+                        }
+                    })),
+            )
+    }
+
+    /// 记录一条日志，只保留最近的 `LOG_LINES` 条
+    pub(crate) fn push_log(&mut self, level: LogLevel, message: String) {
+        if self.log.len() == LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back((level, message));
+    }
+
+    fn render_action_button(&self, palette: &Palette, _cx: &mut Context<Self>) -> Div {
+        let messages = self.messages;
+        let primary = |label: &'static str, bg: Rgba, text: Rgba, msg: UiMsg| {
+            self.button("primary", &self.primary_focus, label, bg, text, palette, msg)
+        };
+
+        match self.status {
+            UpdateStatus::Prompt => div()
                 .w_full()
-                .py(px(6.0))
-                .rounded_lg()
-                .bg(rgb(0x3b82f6))
                 .flex()
-                .items_center()
-                .justify_center()
-                .cursor_pointer()
-                .on_mouse_down(MouseButton::Left, move |_, _, _| {
-                    let _ = retry_tx.try_send(UiMsg::Retry);
-                })
-                .child(div().text_sm().text_color(rgb(0xffffff)).child("重试")),
+                .gap(px(8.0))
+                .child(
+                    self.button(
+                        "secondary",
+                        &self.secondary_focus,
+                        messages.later,
+                        palette.track,
+                        palette.text,
+                        palette,
+                        UiMsg::Consent(false),
+                    )
+                    .flex_1(),
+                )
+                .child(
+                    primary(
+                        messages.install_now,
+                        palette.accent,
+                        rgb(0xffffff),
+                        UiMsg::Consent(true),
+                    )
+                    .flex_1(),
+                ),
+            UpdateStatus::Downloading if self.cancelling => div(),
+            UpdateStatus::Downloading => div().w_full().child(primary(
+                messages.cancel,
+                palette.track,
+                palette.text,
+                UiMsg::Cancel,
+            )),
+            UpdateStatus::Cancelled => div().w_full().child(primary(
+                messages.close,
+                palette.track,
+                palette.text,
+                UiMsg::Quit,
+            )),
+            UpdateStatus::Completed => div().w_full().child(primary(
+                messages.done,
+                palette.success,
+                rgb(0xffffff),
+                UiMsg::Quit,
+            )),
+            UpdateStatus::Failed => div().w_full().child(primary(
+                messages.retry,
+                palette.accent,
+                rgb(0xffffff),
+                UiMsg::Retry,
+            )),
         }
     }
+
+    /// 可以用 Tab 选中、用 Enter 或空格触发的按钮，选中时显示焦点框
+    #[allow(clippy::too_many_arguments)]
+    fn button(
+        &self,
+        id: &'static str,
+        focus: &FocusHandle,
+        label: &'static str,
+        bg: Rgba,
+        text: Rgba,
+        palette: &Palette,
+        msg: UiMsg,
+    ) -> Stateful<Div> {
+        let tx = self.retry_tx.clone();
+        let ring = palette.text;
+        div()
+            .id(id)
+            .track_focus(focus)
+            .w_full()
+            .py(px(6.0))
+            .rounded_lg()
+            .bg(bg)
+            .border_2()
+            .border_color(if bg == palette.track {
+                palette.border
+            } else {
+                bg
+            })
+            .focus(move |style| style.border_color(ring))
+            .flex()
+            .items_center()
+            .justify_center()
+            .cursor_pointer()
+            .on_click(move |_, _, _| {
+                let _ = tx.try_send(msg.clone());
+            })
+            .child(div().text_sm().text_color(text).child(label))
+    }
+}
+
+/// 带单位的大小，例如 `12.3 MB`
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// 进度环，`progress` 为空时画一段从 `offset`（以整圈为 1）开始转动的圆弧
+fn progress_ring(progress: Option<f32>, offset: f32, color: Rgba, track: Rgba) -> impl IntoElement {
+    canvas(
+        |_, _, _| {},
+        move |bounds, _, window, _| {
+            paint_arc(bounds, 0.0, 1.0, track, window);
+            match progress {
+                Some(progress) => paint_arc(bounds, 0.0, progress, color, window),
+                None => paint_arc(bounds, offset, SPINNER_SWEEP, color, window),
+            }
+        },
+    )
+    .absolute()
+    .size_full()
+}
+
+/// 从顶部顺时针画一段圆弧，`start`、`sweep` 以整圈为 1
+fn paint_arc(bounds: Bounds<Pixels>, start: f32, sweep: f32, color: Rgba, window: &mut Window) {
+    if sweep <= 0.0 {
+        return;
+    }
+    let center = bounds.center();
+    let radius = (bounds.size.width.min(bounds.size.height) - px(RING_WIDTH)) / 2.0;
+    let steps = ((sweep * 96.0).ceil() as usize).max(2);
+    let mut builder = PathBuilder::stroke(px(RING_WIDTH));
+    for step in 0..=steps {
+        let angle = (start + sweep * step as f32 / steps as f32) * TAU - FRAC_PI_2;
+        let to = point(center.x + radius * angle.cos(), center.y + radius * angle.sin());
+        if step == 0 {
+            builder.move_to(to);
+        } else {
+            builder.line_to(to);
+        }
+    }
+    if let Ok(path) = builder.build() {
+        window.paint_path(path, color);
+    }
 }
@@ -1,5 +1,121 @@
+use std::{path::Path, time::Duration};
+
+use serde_derive::Serialize;
+
+/// 更新流程所处的阶段
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdatePhase {
+    /// 从更新服务器下载更新配置和更新文件
+    Downloading,
+    /// 读取更新配置
+    Preparing,
+    /// 结束未关闭的electron进程
+    KillingElectron,
+    /// 检查权限并备份旧文件
+    BackingUp,
+    /// 迁移文件
+    Copying,
+    /// 更新失败后回滚文件
+    RollingBack,
+    /// 清理更新文件
+    Cleaning,
+    /// 重启electron程序
+    Relaunching,
+}
+
+/// 更新失败的原因，每一类对应一个独立的退出码
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailReason {
+    /// 运行状态文件（.running_status）无法读写
+    RunningState,
+    /// 无法确定electron程序的安装目录
+    InstallDir,
+    /// 更新配置读取失败
+    Manifest,
+    /// 检查权限或备份旧文件失败
+    Permission,
+    /// 迁移文件失败，已回滚
+    Copy,
+    /// 更新完成但重启electron程序失败
+    Relaunch,
+    /// 上次更新失败，本次已完成回滚
+    RolledBack,
+    /// 文件与更新配置中记录的不一致
+    Integrity,
+    /// 配置文件或参数无效
+    Config,
+    /// 从更新服务器下载更新失败
+    Download,
+    /// 代理地址无效、无法连接代理或代理要求认证
+    Proxy,
+    /// 服务器证书校验失败或 CA 证书文件无效
+    Certificate,
+    /// 更新被取消，已回滚并重启旧版本
+    Cancelled,
+}
+
+impl UpdatePhase {
+    /// 该阶段是否通过 `on_progress` 报告进度，进入时进度从 0 重新开始
+    pub fn reports_progress(self) -> bool {
+        matches!(self, UpdatePhase::Downloading | UpdatePhase::Copying)
+    }
+}
+
+impl FailReason {
+    /// 无界面运行时使用的退出码，0 和 1 分别保留给成功和未知错误
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailReason::RunningState => 2,
+            FailReason::InstallDir => 3,
+            FailReason::Manifest => 4,
+            FailReason::Permission => 5,
+            FailReason::Copy => 6,
+            FailReason::Relaunch => 7,
+            FailReason::RolledBack => 8,
+            FailReason::Integrity => 9,
+            FailReason::Config => 10,
+            FailReason::Download => 11,
+            FailReason::Proxy => 12,
+            FailReason::Certificate => 13,
+            FailReason::Cancelled => 14,
+        }
+    }
+}
+
+/// 询问用户是否安装更新时显示的内容
+#[derive(Clone, Debug)]
+pub struct UpdateSummary {
+    /// 新版本的版本号，更新配置中没有时为空
+    pub version: Option<String>,
+    /// 需要迁移的文件的总字节数
+    pub size: u64,
+    /// 当前语言的更新说明（Markdown）
+    pub release_notes: Option<String>,
+}
+
 pub trait UpdateUi {
+    /// 当前阶段（下载更新或迁移文件）的进度，0 到 1；进入这两个阶段时从 0 重新开始，达到 1 不表示更新完成
     fn on_progress(&self, _progress: f64) {}
+    /// 已迁移 `done` 字节，共 `total` 字节；`eta` 为按平滑后的速度估计的剩余时间，还无法估计时为空
+    fn on_bytes(&self, _done: u64, _total: u64, _eta: Option<Duration>) {}
+    fn on_phase(&self, _phase: UpdatePhase) {}
+    /// 开始迁移第 `current` 个文件（从 1 开始），共 `total` 个
+    fn on_file(&self, _current: usize, _total: usize, _path: &Path) {}
+    /// 当前语言的更新说明（Markdown），读取更新配置后通知，没有说明时不通知
+    fn on_release_notes(&self, _markdown: &str) {}
+    /// 更新失败时调用，不带失败原因，保留给旧的实现
+    #[deprecated(note = "请实现 on_failed_with，它会带上失败原因")]
     fn on_failed(&self) {}
+    /// 更新失败时调用，默认转发给 `on_failed`
+    fn on_failed_with(&self, _reason: FailReason) {
+        #[allow(deprecated)]
+        self.on_failed();
+    }
+    /// 配置了 `ui.confirm` 时在结束 electron 之前调用，阻塞到用户作出选择，返回是否立即安装
+    fn confirm(&self, _summary: &UpdateSummary) -> bool {
+        true
+    }
     fn on_quit(&self);
 }
@@ -0,0 +1,107 @@
+//! 下载使用的 HTTP 客户端
+//!
+//! 未设置 `proxy` 时使用环境变量 `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY` 和 `NO_PROXY`（大小写均可）。
+//! `ca_bundle` 中的证书与内置的根证书一起使用，用于代理解密 TLS 或内网服务器使用企业 CA 的情况。
+
+use std::{env, fs, path::Path};
+
+use ureq::{
+    tls::{Certificate, PemItem, RootCerts, TlsConfig},
+    Agent, Proxy,
+};
+
+use crate::{
+    logging::{Log, Logger},
+    update::{callbacks::FailReason, options::DownloadOptions},
+};
+
+/// 按 `options` 中的代理和证书设置创建客户端
+pub(super) fn agent(options: &DownloadOptions) -> Result<Agent, FailReason> {
+    let proxy = match &options.proxy {
+        Some(url) => Some(proxy(url, &options.no_proxy)?),
+        None => Proxy::try_from_env(),
+    };
+    if let Some(proxy) = &proxy {
+        Log::info(
+            format!(
+                "使用代理 {}:{}{}",
+                proxy.host(),
+                proxy.port(),
+                if proxy.is_from_env() {
+                    "（来自环境变量）"
+                } else {
+                    ""
+                }
+            )
+            .as_str(),
+        );
+    }
+    let mut config = Agent::config_builder().proxy(proxy);
+    if let Some(path) = &options.ca_bundle {
+        config = config.tls_config(TlsConfig::builder().root_certs(root_certs(path)?).build());
+    }
+    Ok(config.build().into())
+}
+
+/// 解析 `url`，`no_proxy` 为空时使用环境变量 `NO_PROXY`
+fn proxy(url: &str, no_proxy: &[String]) -> Result<Proxy, FailReason> {
+    let invalid = |e: ureq::Error| {
+        Log::error(format!("代理地址无效: {e}").as_str());
+        FailReason::Proxy
+    };
+    let parsed = Proxy::new(url).map_err(invalid)?;
+    let mut builder = Proxy::builder(parsed.protocol())
+        .host(parsed.host())
+        .port(parsed.port());
+    if let Some(username) = parsed.username() {
+        builder = builder.username(username);
+    }
+    if let Some(password) = parsed.password() {
+        builder = builder.password(password);
+    }
+    let env_no_proxy = env::var("NO_PROXY")
+        .or_else(|_| env::var("no_proxy"))
+        .unwrap_or_default();
+    let entries: Vec<&str> = if no_proxy.is_empty() {
+        env_no_proxy.split(',').collect()
+    } else {
+        no_proxy.iter().map(String::as_str).collect()
+    };
+    for entry in entries.iter().map(|entry| entry.trim()) {
+        if !entry.is_empty() {
+            builder = builder.no_proxy(entry);
+        }
+    }
+    builder.build().map_err(invalid)
+}
+
+/// 内置的根证书加上 `path` 中的证书
+fn root_certs(path: &Path) -> Result<RootCerts, FailReason> {
+    let location = path.to_string_lossy();
+    let pem = fs::read(path).map_err(|e| {
+        Log::error(format!("读取 CA 证书 {location} 失败: {e}").as_str());
+        FailReason::Certificate
+    })?;
+    let mut extra = Vec::new();
+    for item in ureq::tls::parse_pem(&pem) {
+        match item {
+            Ok(PemItem::Certificate(cert)) => extra.push(cert),
+            Ok(_) => {}
+            Err(e) => {
+                Log::error(format!("CA 证书 {location} 格式无效: {e}").as_str());
+                return Err(FailReason::Certificate);
+            }
+        }
+    }
+    if extra.is_empty() {
+        Log::error(format!("CA 证书 {location} 中没有证书").as_str());
+        return Err(FailReason::Certificate);
+    }
+    Log::info(format!("信任 {location} 中的 {} 个 CA 证书", extra.len()).as_str());
+    let certs: Vec<Certificate<'static>> = webpki_root_certs::TLS_SERVER_ROOT_CERTS
+        .iter()
+        .map(|der| Certificate::from_der(der.as_ref()))
+        .chain(extra)
+        .collect();
+    Ok(RootCerts::new_with_certs(&certs))
+}
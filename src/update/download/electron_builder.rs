@@ -0,0 +1,691 @@
+//! electron-builder 发布的更新源
+//!
+//! 读取 `{base_url}/latest-linux.yml`（Windows 为 `latest.yml`，macOS 为 `latest-mac.yml`；
+//! 设置了 `stable` 以外的发布渠道时，`latest` 替换为渠道名，例如 `beta-linux.yml`），
+//! 其中的版本比 `current_version` 新时下载对应的安装包，作为安装目录中 `artifact` 的新版本交给
+//! `update()` 迁移。服务器上新旧两个版本都有 `.blockmap` 时只下载变化的块，其余块从安装目录中的
+//! 旧文件复制；没有 `.blockmap` 或重建的文件摘要不一致时下载完整的文件。镜像按顺序作为后备来源，
+//! 本地目录来源直接复制完整的文件。
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use base64::Engine;
+use flate2::read::GzDecoder;
+use serde_derive::Deserialize;
+
+use crate::{
+    logging::{Log, Logger},
+    update::{
+        callbacks::{FailReason, UpdateUi},
+        hash::{to_hex, HashAlgorithm},
+        ops::cancel_requested,
+        options::DownloadOptions,
+        state::{is_relative_path, FileHashAndPath, UpdateConfigJson},
+        verify::normalize,
+    },
+};
+
+use super::{
+    classify, content_range, fetch, fetch_file_first, fetch_first, matches_hash, rollout,
+    source::Source, with_retry, write_config, DownloadReport, DownloadedFile, Error,
+};
+
+/// 单次按范围下载的最大字节数，相邻的变化块会合并成一次请求
+const MAX_RANGE: u64 = 4 * 1024 * 1024;
+
+#[derive(Deserialize, Debug)]
+struct Feed {
+    version: String,
+    #[serde(default)]
+    files: Vec<FeedFile>,
+    path: Option<String>,
+    sha512: Option<String>,
+    /// 分阶段发布的比例（0 到 100）
+    #[serde(rename = "stagingPercentage")]
+    staging_percentage: Option<f64>,
+    /// 更新说明，为字符串或 `{version, note}` 的列表
+    #[serde(rename = "releaseNotes")]
+    release_notes: Option<serde_yaml::Value>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct FeedFile {
+    url: String,
+    /// base64 编码的 SHA-512
+    sha512: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct BlockMap {
+    files: Vec<BlockMapFile>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BlockMapFile {
+    offset: u64,
+    checksums: Vec<String>,
+    sizes: Vec<u64>,
+}
+
+/// 重建新文件的一步
+#[derive(Debug, PartialEq, Eq)]
+enum Operation {
+    /// 从旧文件的 `offset` 处复制 `size` 字节
+    Copy { offset: u64, size: u64 },
+    /// 从新文件的 `offset` 处下载 `size` 字节
+    Download { offset: u64, size: u64 },
+}
+
+fn feed_file_name(channel: Option<&str>) -> String {
+    let channel = match channel {
+        None | Some("stable") => "latest",
+        Some(channel) => channel,
+    };
+    if cfg!(target_os = "windows") {
+        format!("{channel}.yml")
+    } else if cfg!(target_os = "macos") {
+        format!("{channel}-mac.yml")
+    } else {
+        format!("{channel}-linux.yml")
+    }
+}
+
+pub(super) fn download(
+    ui: &impl UpdateUi,
+    agent: &ureq::Agent,
+    options: &DownloadOptions,
+    sources: &[Source],
+    exe_path: &Path,
+    update_temp_path: &Path,
+    config_file_name: &str,
+) -> Result<Option<DownloadReport>, FailReason> {
+    let Some(current_version) = options.current_version.as_deref() else {
+        Log::error("使用 electron-builder 更新源时需要设置当前版本 current_version");
+        return Err(FailReason::Config);
+    };
+    let Some(install_dir) = exe_path.parent() else {
+        Log::error("无法获取根目录");
+        return Err(FailReason::InstallDir);
+    };
+    let (_, feed, feed_source) = fetch_first(
+        agent,
+        options,
+        sources,
+        &feed_file_name(options.channel.as_deref()),
+        |data| serde_yaml::from_slice::<Feed>(data).map_err(|e| e.to_string()),
+    )?;
+    Log::info(format!("当前版本 {current_version}，最新版本 {}", feed.version).as_str());
+    if compare_versions(&feed.version, current_version) != Ordering::Greater {
+        Log::info("已是最新版本");
+        return Ok(None);
+    }
+    let rollout = feed.staging_percentage;
+    rollout::check_percentage(rollout)?;
+    if rollout.is_some_and(|percentage| !rollout::in_rollout(percentage, Some(&feed.version))) {
+        return Ok(None);
+    }
+
+    let artifact = match &options.artifact {
+        Some(artifact) => artifact.as_path(),
+        None => Path::new(exe_path.file_name().unwrap_or_default()),
+    };
+    if !is_relative_path(&artifact.to_string_lossy()) {
+        Log::error(
+            format!("artifact 必须是安装目录中的相对路径: {}", artifact.to_string_lossy()).as_str(),
+        );
+        return Err(FailReason::Config);
+    }
+    let Some(file) = pick_file(&feed, artifact) else {
+        Log::error("更新源中没有与 artifact 对应的文件");
+        return Err(FailReason::Manifest);
+    };
+    let hash = match base64::engine::general_purpose::STANDARD.decode(&file.sha512) {
+        Ok(digest) if digest.len() == 64 => to_hex(&digest),
+        _ => {
+            Log::error(format!("更新源中的 sha512 无效: {}", file.sha512).as_str());
+            return Err(FailReason::Manifest);
+        }
+    };
+    // 绝对地址只能从该地址下载，相对地址可以从每个来源下载
+    let (file_sources, name) = match file.url.rsplit_once('/') {
+        Some((dir, name)) if file.url.contains("://") => {
+            (vec![Source::Http(dir.to_string())], name.to_string())
+        }
+        _ => (sources.to_vec(), file.url.clone()),
+    };
+    let old_path = install_dir.join(artifact);
+    let target = update_temp_path.join(&hash);
+    ui.on_file(1, 1, &target);
+    let source = if matches_hash(&target, &hash) {
+        Log::info(format!("已存在，跳过下载: {hash}").as_str());
+        None
+    } else {
+        let mut rebuilt = None;
+        if old_path.is_file() {
+            for source in file_sources.iter() {
+                if let Source::Http(_) = source {
+                    if differential(
+                        ui,
+                        agent,
+                        options,
+                        &source.location(&name),
+                        (&feed.version, current_version),
+                        &old_path,
+                        &target,
+                        &hash,
+                    )? {
+                        rebuilt = Some(source.to_string());
+                        break;
+                    }
+                }
+            }
+        }
+        let source = match rebuilt {
+            Some(source) => source,
+            None => {
+                fetch_file_first(agent, options, &file_sources, &name, &target, &hash, |done| {
+                    ui.on_progress(done)
+                })?
+            }
+        };
+        if let Ok(metadata) = fs::metadata(&old_path) {
+            let _ = fs::set_permissions(&target, metadata.permissions());
+        }
+        Some(source)
+    };
+    ui.on_progress(1.0);
+
+    let entry = FileHashAndPath {
+        filePath: normalize(&artifact.to_string_lossy()),
+        hash: hash.clone(),
+    };
+    let release_notes = feed
+        .release_notes
+        .as_ref()
+        .and_then(feed_release_notes)
+        .map(|notes| BTreeMap::from([("default".to_string(), notes)]))
+        .unwrap_or_default();
+    let config = if old_path.exists() {
+        UpdateConfigJson {
+            version: Some(feed.version.clone()),
+            added: Vec::new(),
+            changed: vec![entry],
+            rollout,
+            release_notes,
+        }
+    } else {
+        UpdateConfigJson {
+            version: Some(feed.version.clone()),
+            added: vec![entry],
+            changed: Vec::new(),
+            rollout,
+            release_notes,
+        }
+    };
+    let json = serde_json::to_vec_pretty(&config).unwrap_or_default();
+    write_config(update_temp_path, config_file_name, &json)?;
+    Ok(Some(DownloadReport {
+        config,
+        manifest_source: feed_source.to_string(),
+        files: vec![DownloadedFile { hash, source }],
+    }))
+}
+
+/// 选择 `artifact` 对应的文件：文件名与 `artifact` 相同的文件，没有时使用更新源 `path` 指向的主文件
+///
+/// 扩展名与 `artifact` 不同时不使用，避免用其他类型的安装包覆盖安装目录中的文件
+fn pick_file(feed: &Feed, artifact: &Path) -> Option<FeedFile> {
+    let artifact_name = artifact.file_name()?.to_string_lossy();
+    let file = feed
+        .files
+        .iter()
+        .find(|file| url_file_name(&file.url) == artifact_name)
+        .cloned()
+        .or_else(|| {
+            let path = feed.path.as_ref()?;
+            feed.files
+                .iter()
+                .find(|file| &file.url == path)
+                .cloned()
+                .or_else(|| {
+                    Some(FeedFile {
+                        url: path.clone(),
+                        sha512: feed.sha512.clone()?,
+                    })
+                })
+        })?;
+    let extension = |path: &Path| path.extension().map(|ext| ext.to_ascii_lowercase());
+    if extension(Path::new(url_file_name(&file.url))) != extension(artifact) {
+        Log::error(format!("更新源中的 {} 与 artifact 的类型不同", file.url).as_str());
+        return None;
+    }
+    Some(file)
+}
+
+/// 地址中最后一个 `/` 之后的文件名
+fn url_file_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+/// 按 `.` 和 `-` 分隔比较版本号，数字部分按数值比较；带预发布后缀的版本低于同号的正式版本
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |version: &str| {
+        let version = version.trim_start_matches('v');
+        match version.split_once('-') {
+            Some((core, pre)) => (core.to_string(), Some(pre.to_string())),
+            None => (version.to_string(), None),
+        }
+    };
+    let compare_parts = |a: &str, b: &str| {
+        let mut a = a.split('.');
+        let mut b = b.split('.');
+        loop {
+            let ordering = match (a.next(), b.next()) {
+                (None, None) => return Ordering::Equal,
+                (Some(_), None) => return Ordering::Greater,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    _ => x.cmp(y),
+                },
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+    };
+    let (core_a, pre_a) = split(a);
+    let (core_b, pre_b) = split(b);
+    compare_parts(&core_a, &core_b).then_with(|| match (pre_a, pre_b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(x), Some(y)) => compare_parts(&x, &y),
+    })
+}
+
+/// 把 url 文件名中的新版本号替换为当前版本号，得到旧版本的文件地址
+fn old_url(url: &str, new_version: &str, current_version: &str) -> Option<String> {
+    let (dir, name) = url.rsplit_once('/')?;
+    name.contains(new_version)
+        .then(|| format!("{dir}/{}", name.replace(new_version, current_version)))
+}
+
+fn fetch_blockmap(agent: &ureq::Agent, options: &DownloadOptions, url: &str) -> Option<BlockMap> {
+    let data = with_retry(options, url, || fetch(agent, url)).ok()?;
+    let mut json = Vec::new();
+    if let Err(e) = GzDecoder::new(data.as_slice()).read_to_end(&mut json) {
+        Log::warn(format!("解压 {url} 失败: {e}").as_str());
+        return None;
+    }
+    match serde_json::from_slice(&json) {
+        Ok(blockmap) => Some(blockmap),
+        Err(e) => {
+            Log::warn(format!("{url} 格式无效: {e}").as_str());
+            None
+        }
+    }
+}
+
+/// 展开为 `(checksum, offset, size)` 的列表，偏移超出范围时返回 `None`
+fn blocks(blockmap: &BlockMap) -> Option<Vec<(&str, u64, u64)>> {
+    let mut blocks = Vec::new();
+    for file in blockmap.files.iter() {
+        let mut offset = file.offset;
+        for (checksum, size) in file.checksums.iter().zip(file.sizes.iter()) {
+            blocks.push((checksum.as_str(), offset, *size));
+            offset = offset.checked_add(*size)?;
+        }
+    }
+    Some(blocks)
+}
+
+/// 对比新旧块列表，得到重建新文件的步骤，相邻的同类步骤会合并；blockmap 无效时返回 `None`
+fn plan(old: &BlockMap, new: &BlockMap) -> Option<Vec<Operation>> {
+    let mut old_blocks = HashMap::new();
+    for (checksum, offset, size) in blocks(old)? {
+        old_blocks.entry((checksum, size)).or_insert(offset);
+    }
+    let mut operations: Vec<Operation> = Vec::new();
+    // 空的块不需要复制或下载，按范围下载时也无法表示
+    for (checksum, new_offset, size) in blocks(new)?.into_iter().filter(|block| block.2 > 0) {
+        let operation = match old_blocks.get(&(checksum, size)) {
+            Some(&offset) => Operation::Copy { offset, size },
+            None => Operation::Download {
+                offset: new_offset,
+                size,
+            },
+        };
+        match (operations.last_mut(), operation) {
+            (
+                Some(Operation::Copy { offset, size }),
+                Operation::Copy {
+                    offset: next,
+                    size: next_size,
+                },
+            ) if offset.checked_add(*size) == Some(next) => *size += next_size,
+            (
+                Some(Operation::Download { offset, size }),
+                Operation::Download {
+                    offset: next,
+                    size: next_size,
+                },
+            ) if offset.checked_add(*size) == Some(next)
+                && size
+                    .checked_add(next_size)
+                    .is_some_and(|merged| merged <= MAX_RANGE) =>
+            {
+                *size += next_size
+            }
+            (_, operation) => operations.push(operation),
+        }
+    }
+    Some(operations)
+}
+
+fn fetch_range(agent: &ureq::Agent, url: &str, offset: u64, size: u64) -> Result<Vec<u8>, Error> {
+    let Some(end) = size
+        .checked_sub(1)
+        .and_then(|last| offset.checked_add(last))
+    else {
+        return Err(Error::Fatal(FailReason::Download, format!("无效的下载范围: {offset}+{size}")));
+    };
+    let mut response = agent
+        .get(url)
+        .header("Range", format!("bytes={offset}-{end}"))
+        .call()
+        .map_err(classify)?;
+    let start = response
+        .headers()
+        .get("content-range")
+        .and_then(|value| value.to_str().ok())
+        .and_then(content_range)
+        .map(|(start, _)| start);
+    if response.status().as_u16() != 206 || start != Some(offset) {
+        return Err(Error::Fatal(FailReason::Download, "服务器不支持按范围下载".to_string()));
+    }
+    let mut data = Vec::with_capacity(size as usize);
+    response
+        .body_mut()
+        .as_reader()
+        .take(size)
+        .read_to_end(&mut data)
+        .map_err(|e| Error::Transient(e.to_string()))?;
+    if data.len() as u64 != size {
+        return Err(Error::Transient(format!(
+            "按范围下载的长度不一致: 期望 {size}, 实际 {}",
+            data.len()
+        )));
+    }
+    Ok(data)
+}
+
+/// 按新旧两个版本的 blockmap 重建新文件，无法差量更新时返回 `false`
+#[allow(clippy::too_many_arguments)]
+fn differential(
+    ui: &impl UpdateUi,
+    agent: &ureq::Agent,
+    options: &DownloadOptions,
+    url: &str,
+    (new_version, current_version): (&str, &str),
+    old_path: &Path,
+    target: &Path,
+    hash: &str,
+) -> Result<bool, FailReason> {
+    let Some(old_url) = old_url(url, new_version, current_version) else {
+        Log::info("文件名中不包含版本号，无法找到旧版本的 blockmap");
+        return Ok(false);
+    };
+    let (Some(old), Some(new)) = (
+        fetch_blockmap(agent, options, &format!("{old_url}.blockmap")),
+        fetch_blockmap(agent, options, &format!("{url}.blockmap")),
+    ) else {
+        Log::info(format!("{url} 没有可用的 blockmap").as_str());
+        return Ok(false);
+    };
+    let total = blocks(&new).and_then(|blocks| {
+        blocks
+            .iter()
+            .try_fold(0u64, |total, (_, _, size)| total.checked_add(*size))
+    });
+    let (Some(operations), Some(total)) = (plan(&old, &new), total) else {
+        Log::warn(format!("{url} 的 blockmap 无效").as_str());
+        return Ok(false);
+    };
+    let downloading: u64 = operations
+        .iter()
+        .map(|operation| match operation {
+            Operation::Download { size, .. } => *size,
+            Operation::Copy { .. } => 0,
+        })
+        .sum();
+    Log::info(format!("差量下载: 需要下载 {downloading}/{total} 字节").as_str());
+
+    let part = target.with_extension("part");
+    let result = rebuild(ui, agent, options, url, &operations, total, old_path, &part);
+    if cancel_requested() {
+        let _ = fs::remove_file(&part);
+        Log::info("下载已取消");
+        return Err(FailReason::Download);
+    }
+    if let Err(e) = result {
+        let _ = fs::remove_file(&part);
+        Log::warn(format!("差量下载失败: {e}").as_str());
+        return Ok(false);
+    }
+    let actual = HashAlgorithm::Sha512.hash_file(&part).unwrap_or_default();
+    if actual != hash {
+        let _ = fs::remove_file(&part);
+        Log::warn("差量重建的文件摘要不一致");
+        return Ok(false);
+    }
+    if let Err(e) = fs::rename(&part, target) {
+        Log::warn(format!("保存重建的文件失败: {e}").as_str());
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn rebuild(
+    ui: &impl UpdateUi,
+    agent: &ureq::Agent,
+    options: &DownloadOptions,
+    url: &str,
+    operations: &[Operation],
+    total: u64,
+    old_path: &Path,
+    part: &Path,
+) -> io::Result<()> {
+    let mut old = fs::File::open(old_path)?;
+    let mut out = fs::File::create(part)?;
+    let mut done = 0u64;
+    for operation in operations.iter() {
+        if cancel_requested() {
+            return Err(io::Error::other("下载已取消"));
+        }
+        let size = match *operation {
+            Operation::Copy { offset, size } => {
+                old.seek(SeekFrom::Start(offset))?;
+                let copied = io::copy(&mut (&mut old).take(size), &mut out)?;
+                if copied != size {
+                    return Err(io::Error::other("旧文件比 blockmap 中记录的短"));
+                }
+                size
+            }
+            Operation::Download { offset, size } => {
+                let data = with_retry(options, url, || fetch_range(agent, url, offset, size))
+                    .map_err(|reason| io::Error::other(format!("{reason:?}")))?;
+                out.write_all(&data)?;
+                size
+            }
+        };
+        done += size;
+        if total > 0 {
+            ui.on_progress(done as f64 / total as f64);
+        }
+    }
+    out.flush()
+}
+
+/// 把更新源中的 `releaseNotes` 转成 Markdown，列表中的每一项以版本号为标题
+fn feed_release_notes(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(notes) => Some(notes.clone()),
+        serde_yaml::Value::Sequence(entries) => {
+            let sections: Vec<String> = entries
+                .iter()
+                .filter_map(|entry| {
+                    let note = entry.get("note")?.as_str()?;
+                    Some(match entry.get("version").and_then(|v| v.as_str()) {
+                        Some(version) => format!("## {version}\n\n{note}"),
+                        None => note.to_string(),
+                    })
+                })
+                .collect();
+            (!sections.is_empty()).then(|| sections.join("\n\n"))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blockmap(offset: u64, blocks: &[(&str, u64)]) -> BlockMap {
+        BlockMap {
+            files: vec![BlockMapFile {
+                offset,
+                checksums: blocks
+                    .iter()
+                    .map(|(checksum, _)| checksum.to_string())
+                    .collect(),
+                sizes: blocks.iter().map(|(_, size)| *size).collect(),
+            }],
+        }
+    }
+
+    fn feed(yaml: &str) -> Feed {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn compares_versions() {
+        assert_eq!(compare_versions("1.10.0", "1.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.2.0", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2", "1.2.1"), Ordering::Less);
+        assert_eq!(compare_versions("1.2.0-beta.1", "1.2.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.2.0-beta.10", "1.2.0-beta.2"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2.0-beta", "1.2.0-alpha"), Ordering::Greater);
+    }
+
+    #[test]
+    fn plans_copies_and_downloads() {
+        let old = blockmap(0, &[("a", 10), ("b", 20), ("c", 5)]);
+        let new = blockmap(0, &[("a", 10), ("b", 20), ("d", 7), ("e", 3), ("c", 5)]);
+        assert_eq!(
+            plan(&old, &new),
+            Some(vec![
+                Operation::Copy {
+                    offset: 0,
+                    size: 30
+                },
+                Operation::Download {
+                    offset: 30,
+                    size: 10
+                },
+                Operation::Copy {
+                    offset: 30,
+                    size: 5
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn plan_skips_empty_blocks() {
+        let old = blockmap(0, &[("a", 10)]);
+        let new = blockmap(0, &[("a", 10), ("empty", 0), ("b", 4), ("empty", 0)]);
+        assert_eq!(
+            plan(&old, &new),
+            Some(vec![
+                Operation::Copy {
+                    offset: 0,
+                    size: 10
+                },
+                Operation::Download {
+                    offset: 10,
+                    size: 4
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn plan_rejects_overflowing_offsets() {
+        let old = blockmap(0, &[("a", 10)]);
+        let new = blockmap(u64::MAX - 5, &[("a", 10), ("b", 10)]);
+        assert_eq!(plan(&old, &new), None);
+        assert_eq!(plan(&new, &old), None);
+    }
+
+    #[test]
+    fn plan_limits_range_size() {
+        let old = blockmap(0, &[]);
+        let new = blockmap(0, &[("a", MAX_RANGE), ("b", 1)]);
+        assert_eq!(plan(&old, &new).map(|operations| operations.len()), Some(2));
+    }
+
+    #[test]
+    fn picks_file_by_artifact_name() {
+        let feed = feed(
+            "version: 1.2.0\n\
+             files:\n\
+             - url: App-1.2.0.AppImage\n  sha512: aaa\n\
+             - url: App-1.2.0.deb\n  sha512: bbb\n\
+             path: App-1.2.0.AppImage\n\
+             sha512: aaa\n",
+        );
+        let pick = |artifact: &str| pick_file(&feed, Path::new(artifact)).map(|file| file.url);
+        assert_eq!(pick("App-1.2.0.deb").as_deref(), Some("App-1.2.0.deb"));
+        // 文件名不同时使用 path 指向的主文件
+        assert_eq!(pick("bin/App.AppImage").as_deref(), Some("App-1.2.0.AppImage"));
+        // 类型不同的安装包不能覆盖安装目录中的文件
+        assert_eq!(pick("App.exe"), None);
+    }
+
+    #[test]
+    fn picks_top_level_path_without_files() {
+        let feed = feed("version: 1.2.0\npath: App-1.2.0.AppImage\nsha512: aaa\n");
+        let file = pick_file(&feed, Path::new("App.AppImage")).unwrap();
+        assert_eq!((file.url.as_str(), file.sha512.as_str()), ("App-1.2.0.AppImage", "aaa"));
+    }
+
+    #[test]
+    fn finds_old_url() {
+        assert_eq!(
+            old_url("https://example.com/App-1.2.0.AppImage", "1.2.0", "1.1.0").as_deref(),
+            Some("https://example.com/App-1.1.0.AppImage")
+        );
+        assert_eq!(old_url("https://example.com/App.AppImage", "1.2.0", "1.1.0"), None);
+    }
+
+    #[test]
+    fn converts_release_notes() {
+        let notes = serde_yaml::from_str(
+            "- version: 1.2.0\n  note: Fixes\n- version: 1.1.0\n  note: Features\n",
+        )
+        .unwrap();
+        assert_eq!(
+            feed_release_notes(&notes).as_deref(),
+            Some("## 1.2.0\n\nFixes\n\n## 1.1.0\n\nFeatures")
+        );
+    }
+}
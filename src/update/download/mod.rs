@@ -0,0 +1,770 @@
+//! 从更新服务器下载更新
+//!
+//! 默认的更新服务器目录结构与 `generate` 生成的更新目录一致：`{base_url}/{update_config_file_name}`
+//! 为更新配置，`{base_url}/{hash}` 为对应的更新文件；设置了发布渠道时改为 `{base_url}/{channel}/` 下的
+//! 文件。`mirrors` 中的镜像按顺序作为后备来源，见 [`source`]。`format = "electron-builder"` 时改为读取 electron-builder 发布的更新源，见 [`electron_builder`]。
+//! 更新配置中的 `rollout` 为分阶段发布的比例，见 [`rollout`]。代理和额外的 CA 证书见 [`client`]。
+//!
+//! 下载中的文件保存为 `{hash}.part`，同时在 `{hash}.part.json` 中记录期望的大小、摘要和服务器返回的
+//! `ETag`/`Last-Modified`。中断后再次下载时使用 `Range` 和 `If-Range` 请求剩余部分，服务器上的文件
+//! 已变化时从头下载。网络错误按指数退避重试，重试次数用完后才报告失败。
+
+mod client;
+mod electron_builder;
+mod rollout;
+mod source;
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::logging::{Log, Logger};
+
+use super::{
+    callbacks::{FailReason, UpdatePhase, UpdateUi},
+    hash::HashAlgorithm,
+    ops::cancel_requested,
+    options::{DownloadFormat, DownloadOptions},
+    state::UpdateConfigJson,
+};
+
+use self::source::Source;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+/// 两次重试之间最长的等待时间
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// 下载一次失败的结果
+enum Error {
+    /// 网络错误等，可以重试
+    Transient(String),
+    /// 重试也不会成功的错误
+    Fatal(FailReason, String),
+}
+
+/// 未下载完的文件的信息，保存在 `{hash}.part.json` 中
+#[derive(Serialize, Deserialize, Debug)]
+struct PartialDownload {
+    url: String,
+    hash: String,
+    /// 文件的总大小，服务器未返回时为空
+    size: Option<u64>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl PartialDownload {
+    /// `If-Range` 使用的校验值，优先使用 `ETag`
+    fn validator(&self) -> Option<&str> {
+        self.etag.as_deref().or(self.last_modified.as_deref())
+    }
+}
+
+/// 下载到更新目录中的一个文件
+#[derive(Debug)]
+pub struct DownloadedFile {
+    pub hash: String,
+    /// 提供该文件的来源，更新目录中已有该文件时为空
+    pub source: Option<String>,
+}
+
+/// 下载的结果
+#[derive(Debug)]
+pub struct DownloadReport {
+    /// 写入更新目录的更新配置
+    pub config: UpdateConfigJson,
+    /// 提供更新配置（或 electron-builder 更新源）的来源
+    pub manifest_source: String,
+    pub files: Vec<DownloadedFile>,
+}
+
+/// 下载更新到 `update_temp_path`，并在其中写入名为 `config_file_name` 的更新配置
+///
+/// 依次尝试 `base_url` 和 `mirrors` 中的来源，每个文件在出错或摘要不一致时换下一个来源。
+/// 更新配置在所有文件下载完成后最后写入，因此更新目录中出现更新配置即表示下载完整。
+/// 更新服务器上没有比当前更新的版本，或本机不在分阶段发布范围内时返回 `None`
+pub fn download(
+    ui: &impl UpdateUi,
+    options: &DownloadOptions,
+    exe_path: &Path,
+    update_temp_path: &Path,
+    config_file_name: &str,
+) -> Result<Option<DownloadReport>, FailReason> {
+    let sources = sources(options);
+    if sources.is_empty() {
+        Log::error("未设置可用的更新服务器地址");
+        return Err(FailReason::Config);
+    }
+    ui.on_phase(UpdatePhase::Downloading);
+    Log::info("下载更新，来源: ");
+    for source in sources.iter() {
+        Log::info(source.to_string().as_str());
+    }
+    if let Err(e) = fs::create_dir_all(update_temp_path) {
+        Log::error("创建更新目录失败");
+        Log::error(e.to_string().as_str());
+        return Err(FailReason::Download);
+    }
+    let agent = client::agent(options)?;
+    let report = match options.format {
+        DownloadFormat::Manifest => {
+            download_manifest(ui, &agent, options, &sources, update_temp_path, config_file_name)?
+        }
+        DownloadFormat::ElectronBuilder => electron_builder::download(
+            ui,
+            &agent,
+            options,
+            &sources,
+            exe_path,
+            update_temp_path,
+            config_file_name,
+        )?,
+    };
+    if let Some(report) = &report {
+        Log::info(format!("下载更新完成，更新配置来自 {}", report.manifest_source).as_str());
+        for file in report.files.iter() {
+            match &file.source {
+                Some(source) => Log::info(format!("{} 来自 {source}", file.hash).as_str()),
+                None => Log::info(format!("{} 已存在于更新目录", file.hash).as_str()),
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// `base_url` 和 `mirrors` 中可用的来源，按尝试的顺序排列
+fn sources(options: &DownloadOptions) -> Vec<Source> {
+    options
+        .base_url
+        .iter()
+        .chain(options.mirrors.iter())
+        .filter_map(|location| {
+            let source = Source::parse(location);
+            if source.is_none() {
+                Log::warn(format!("无法识别的下载来源，已忽略: {location}").as_str());
+            }
+            source
+        })
+        .collect()
+}
+
+/// 依次从 `sources` 中读取名为 `name` 的文件，直到 `parse` 成功，返回结果和对应的来源
+fn fetch_first<T>(
+    agent: &ureq::Agent,
+    options: &DownloadOptions,
+    sources: &[Source],
+    name: &str,
+    parse: impl Fn(&[u8]) -> Result<T, String>,
+) -> Result<(Vec<u8>, T, Source), FailReason> {
+    let mut reason = FailReason::Download;
+    for source in sources.iter() {
+        match source.fetch(agent, options, name) {
+            Ok(data) => match parse(&data) {
+                Ok(value) => return Ok((data, value, source.clone())),
+                Err(e) => {
+                    Log::warn(format!("{} 格式无效: {e}", source.location(name)).as_str());
+                    reason = FailReason::Manifest;
+                }
+            },
+            Err(e) => reason = e,
+        }
+        if cancel_requested() {
+            return Err(FailReason::Download);
+        }
+        Log::warn(format!("无法从 {source} 获取 {name}，尝试下一个来源").as_str());
+    }
+    Err(reason)
+}
+
+/// 依次从 `sources` 中下载名为 `name` 的文件，返回提供该文件的来源
+#[allow(clippy::too_many_arguments)]
+fn fetch_file_first(
+    agent: &ureq::Agent,
+    options: &DownloadOptions,
+    sources: &[Source],
+    name: &str,
+    target: &Path,
+    hash: &str,
+    on_progress: impl Fn(f64),
+) -> Result<String, FailReason> {
+    let mut reason = FailReason::Download;
+    for source in sources.iter() {
+        match source.fetch_file(agent, options, name, target, hash, &on_progress) {
+            Ok(()) => return Ok(source.to_string()),
+            Err(e) => reason = e,
+        }
+        if cancel_requested() {
+            return Err(FailReason::Download);
+        }
+        Log::warn(format!("无法从 {source} 获取 {name}，尝试下一个来源").as_str());
+    }
+    Err(reason)
+}
+
+/// 下载更新配置和其中引用的所有更新文件，本机不在分阶段发布范围内时返回 `None`
+///
+/// 每个文件先写入 `{hash}.part`，校验摘要后再改名；已存在且摘要一致的文件不会重复下载
+fn download_manifest(
+    ui: &impl UpdateUi,
+    agent: &ureq::Agent,
+    options: &DownloadOptions,
+    sources: &[Source],
+    update_temp_path: &Path,
+    config_file_name: &str,
+) -> Result<Option<DownloadReport>, FailReason> {
+    let sources: Vec<Source> = match options.channel.as_deref() {
+        Some(channel) => sources.iter().map(|source| source.join(channel)).collect(),
+        None => sources.to_vec(),
+    };
+    let (manifest, config, manifest_source) =
+        fetch_first(agent, options, &sources, config_file_name, |data| {
+            serde_json::from_slice::<UpdateConfigJson>(data).map_err(|e| e.to_string())
+        })?;
+    rollout::check_percentage(config.rollout)?;
+    if config
+        .rollout
+        .is_some_and(|percentage| !rollout::in_rollout(percentage, config.version.as_deref()))
+    {
+        return Ok(None);
+    }
+
+    let mut seen = HashSet::new();
+    let hashes: Vec<&str> = config
+        .added
+        .iter()
+        .chain(config.changed.iter())
+        .map(|item| item.hash.as_str())
+        .filter(|hash| seen.insert(*hash))
+        .collect();
+    // 下载的文件必须经过校验，摘要无法识别的更新配置直接拒绝
+    if let Some(hash) = hashes
+        .iter()
+        .find(|hash| HashAlgorithm::detect(hash).is_none())
+    {
+        Log::error(format!("更新配置中的摘要无法识别: {hash}").as_str());
+        return Err(FailReason::Manifest);
+    }
+
+    let total = hashes.len();
+    let mut files = Vec::with_capacity(total);
+    for (index, hash) in hashes.iter().enumerate() {
+        let target = update_temp_path.join(hash);
+        ui.on_file(index + 1, total, &target);
+        let source = if matches_hash(&target, hash) {
+            Log::info(format!("已存在，跳过下载: {hash}").as_str());
+            None
+        } else {
+            Some(fetch_file_first(agent, options, &sources, hash, &target, hash, |done| {
+                ui.on_progress((index as f64 + done) / total as f64)
+            })?)
+        };
+        files.push(DownloadedFile {
+            hash: hash.to_string(),
+            source,
+        });
+        ui.on_progress((index + 1) as f64 / total as f64);
+    }
+
+    write_config(update_temp_path, config_file_name, &manifest)?;
+    Ok(Some(DownloadReport {
+        config,
+        manifest_source: manifest_source.to_string(),
+        files,
+    }))
+}
+
+fn write_config(
+    update_temp_path: &Path,
+    config_file_name: &str,
+    json: &[u8],
+) -> Result<(), FailReason> {
+    fs::write(update_temp_path.join(config_file_name), json).map_err(|e| {
+        Log::error("写入更新配置失败");
+        Log::error(e.to_string().as_str());
+        FailReason::Download
+    })
+}
+
+fn join_url(base_url: &str, name: &str) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), name)
+}
+
+/// 执行 `attempt`，可以重试的错误按指数退避重试 `options.retries` 次
+fn with_retry<T>(
+    options: &DownloadOptions,
+    url: &str,
+    mut attempt: impl FnMut() -> Result<T, Error>,
+) -> Result<T, FailReason> {
+    let mut retried = 0;
+    loop {
+        match attempt() {
+            Ok(value) => return Ok(value),
+            Err(Error::Fatal(reason, message)) => {
+                Log::error(format!("下载 {url} 失败: {message}").as_str());
+                return Err(reason);
+            }
+            Err(Error::Transient(message)) => {
+                Log::warn(format!("下载 {url} 失败: {message}").as_str());
+                if retried >= options.retries {
+                    Log::error(format!("下载 {url} 失败，已重试 {retried} 次").as_str());
+                    return Err(FailReason::Download);
+                }
+                let delay = options
+                    .retry_delay()
+                    .saturating_mul(1 << retried.min(16))
+                    .min(MAX_RETRY_DELAY);
+                retried += 1;
+                Log::info(
+                    format!("{} 毫秒后第 {retried}/{} 次重试", delay.as_millis(), options.retries)
+                        .as_str(),
+                );
+                if !sleep_unless_cancelled(delay) {
+                    Log::info("下载已取消");
+                    return Err(FailReason::Download);
+                }
+            }
+        }
+    }
+}
+
+/// 等待 `delay`，期间收到取消请求时提前返回 `false`
+fn sleep_unless_cancelled(delay: Duration) -> bool {
+    let step = Duration::from_millis(100);
+    let mut waited = Duration::ZERO;
+    while waited < delay {
+        if cancel_requested() {
+            return false;
+        }
+        let next = step.min(delay - waited);
+        thread::sleep(next);
+        waited += next;
+    }
+    !cancel_requested()
+}
+
+fn classify(e: ureq::Error) -> Error {
+    match e {
+        ureq::Error::StatusCode(407) => {
+            Error::Fatal(FailReason::Proxy, "代理要求认证（407）".to_string())
+        }
+        ureq::Error::InvalidProxyUrl | ureq::Error::ConnectProxyFailed(_) => {
+            Error::Fatal(FailReason::Proxy, e.to_string())
+        }
+        ureq::Error::Tls(_)
+        | ureq::Error::Pem(_)
+        | ureq::Error::Rustls(_)
+        | ureq::Error::TlsRequired => Error::Fatal(FailReason::Certificate, e.to_string()),
+        ureq::Error::StatusCode(code) if code == 408 || code == 429 || code >= 500 => {
+            Error::Transient(format!("服务器返回 {code}"))
+        }
+        ureq::Error::StatusCode(code) => {
+            Error::Fatal(FailReason::Download, format!("服务器返回 {code}"))
+        }
+        // 握手失败时 rustls 的错误包装在 io::Error 中
+        ureq::Error::Io(ref io)
+            if io
+                .get_ref()
+                .is_some_and(|inner| inner.is::<rustls::Error>()) =>
+        {
+            Error::Fatal(FailReason::Certificate, e.to_string())
+        }
+        ureq::Error::Io(_)
+        | ureq::Error::Timeout(_)
+        | ureq::Error::HostNotFound
+        | ureq::Error::ConnectionFailed
+        | ureq::Error::Protocol(_) => Error::Transient(e.to_string()),
+        e => Error::Fatal(FailReason::Download, e.to_string()),
+    }
+}
+
+fn io_error(message: &str, e: std::io::Error) -> Error {
+    Error::Fatal(FailReason::Download, format!("{message}: {e}"))
+}
+
+fn fetch(agent: &ureq::Agent, url: &str) -> Result<Vec<u8>, Error> {
+    Log::info(format!("下载: {url}").as_str());
+    agent
+        .get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_vec())
+        .map_err(classify)
+}
+
+/// 文件存在且摘要与 `hash` 一致；无法识别摘要算法时视为不一致
+fn matches_hash(path: &Path, hash: &str) -> bool {
+    let Some(algorithm) = HashAlgorithm::detect(hash) else {
+        return false;
+    };
+    path.is_file()
+        && algorithm
+            .hash_file(path)
+            .is_ok_and(|actual| actual.eq_ignore_ascii_case(hash))
+}
+
+fn part_paths(target: &Path) -> (PathBuf, PathBuf) {
+    (target.with_extension("part"), target.with_extension("part.json"))
+}
+
+/// 读取可以继续下载的部分，返回已下载的字节数和记录的信息
+fn resumable(target: &Path, url: &str, hash: &str) -> Option<(u64, PartialDownload)> {
+    let (part, meta) = part_paths(target);
+    let partial: PartialDownload = serde_json::from_slice(&fs::read(meta).ok()?).ok()?;
+    if partial.url != url || partial.hash != hash || partial.validator().is_none() {
+        return None;
+    }
+    let len = fs::metadata(part).ok()?.len();
+    match partial.size {
+        Some(size) if len >= size => None,
+        _ if len == 0 => None,
+        _ => Some((len, partial)),
+    }
+}
+
+fn remove_partial(target: &Path) {
+    let (part, meta) = part_paths(target);
+    let _ = fs::remove_file(part);
+    let _ = fs::remove_file(meta);
+}
+
+/// `Content-Range: bytes {start}-{end}/{total}` 中的起始位置和总大小
+fn content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let start = range.split_once('-')?.0.parse().ok()?;
+    Some((start, total.parse().ok()))
+}
+
+fn download_file(
+    agent: &ureq::Agent,
+    url: &str,
+    target: &Path,
+    hash: &str,
+    on_progress: impl Fn(f64),
+) -> Result<(), Error> {
+    let (part, meta) = part_paths(target);
+    let resume = resumable(target, url, hash);
+    let mut request = agent.get(url);
+    match &resume {
+        Some((offset, partial)) => {
+            Log::info(format!("继续下载: {url}，已下载 {offset} 字节").as_str());
+            request = request.header("Range", format!("bytes={offset}-"));
+            if let Some(validator) = partial.validator() {
+                request = request.header("If-Range", validator);
+            }
+        }
+        None => {
+            Log::info(format!("下载: {url}").as_str());
+            remove_partial(target);
+        }
+    }
+    let mut response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::StatusCode(416)) => {
+            Log::warn("服务器不接受继续下载的范围，从头下载");
+            remove_partial(target);
+            return Err(Error::Transient("服务器返回 416".to_string()));
+        }
+        Err(e) => return Err(classify(e)),
+    };
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let content_range = header("content-range").and_then(|value| content_range(&value));
+    let (offset, size) = match (&resume, response.status().as_u16(), content_range) {
+        (Some((offset, partial)), 206, Some((start, total))) if start == *offset => {
+            (*offset, total.or(partial.size))
+        }
+        _ => {
+            if resume.is_some() {
+                Log::info("服务器上的文件已变化或不支持继续下载，从头下载");
+            }
+            (0, response.body().content_length())
+        }
+    };
+    let partial = PartialDownload {
+        url: url.to_string(),
+        hash: hash.to_string(),
+        size,
+        etag: header("etag"),
+        last_modified: header("last-modified"),
+    };
+    let json = serde_json::to_vec(&partial).unwrap_or_default();
+    fs::write(&meta, json).map_err(|e| io_error("写入下载记录失败", e))?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(offset > 0)
+        .truncate(offset == 0)
+        .open(&part)
+        .map_err(|e| io_error("创建下载文件失败", e))?;
+
+    let mut reader = response.body_mut().as_reader();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut done = offset;
+    loop {
+        if cancel_requested() {
+            return Err(Error::Fatal(FailReason::Download, "下载已取消".to_string()));
+        }
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| Error::Transient(e.to_string()))?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])
+            .map_err(|e| io_error("写入下载文件失败", e))?;
+        done += read as u64;
+        if let Some(size) = size.filter(|size| *size > 0) {
+            on_progress((done as f64 / size as f64).min(1.0));
+        }
+    }
+    file.flush().map_err(|e| io_error("写入下载文件失败", e))?;
+    drop(file);
+    if let Some(size) = size.filter(|size| done < *size) {
+        return Err(Error::Transient(format!("连接提前断开，已下载 {done}/{size} 字节")));
+    }
+
+    let Some(algorithm) = HashAlgorithm::detect(hash) else {
+        remove_partial(target);
+        return Err(Error::Fatal(
+            FailReason::Integrity,
+            format!("无法识别摘要算法，无法校验下载的文件: {hash}"),
+        ));
+    };
+    let actual = algorithm
+        .hash_file(&part)
+        .map_err(|e| io_error("计算下载文件摘要失败", e))?;
+    if !actual.eq_ignore_ascii_case(hash) {
+        remove_partial(target);
+        let message = format!("下载文件摘要不一致: 期望 {hash}, 实际 {actual}");
+        // 续传的文件可能拼接了不同版本的内容，从头下载一次
+        return Err(if offset > 0 {
+            Error::Transient(message)
+        } else {
+            Error::Fatal(FailReason::Integrity, message)
+        });
+    }
+    fs::rename(&part, target).map_err(|e| io_error("保存下载文件失败", e))?;
+    let _ = fs::remove_file(&meta);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::update::{hash::to_hex, test_support::TempDir};
+
+    const ETAG: &str = "\"v2\"";
+
+    /// 本地的 HTTP 服务器，提供 `content`，支持 `Range` 和 `If-Range`；记录收到的请求头（小写）
+    struct Server {
+        url: String,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Server {
+        fn start(content: &'static [u8]) -> Server {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}/file", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let recorded = requests.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(mut stream) = stream else {
+                        continue;
+                    };
+                    let mut request = String::new();
+                    let mut reader = BufReader::new(&stream);
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                            break;
+                        }
+                        request.push_str(&line.to_ascii_lowercase());
+                    }
+                    let response = respond(&request, content);
+                    recorded.lock().unwrap().push(request);
+                    let _ = stream.write_all(&response);
+                }
+            });
+            Server { url, requests }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn respond(request: &str, content: &[u8]) -> Vec<u8> {
+        let header = |name: &str| {
+            request
+                .lines()
+                .find_map(|line| line.strip_prefix(name))
+                .map(|value| value.trim().to_string())
+        };
+        let start = header("range:")
+            .filter(|_| header("if-range:").is_none_or(|validator| validator == ETAG))
+            .and_then(|range| {
+                range
+                    .strip_prefix("bytes=")?
+                    .strip_suffix('-')?
+                    .parse()
+                    .ok()
+            })
+            .filter(|start: &usize| *start < content.len());
+        let (status, extra, body) = match start {
+            Some(start) => (
+                "206 Partial Content",
+                format!("Content-Range: bytes {start}-{}/{}\r\n", content.len() - 1, content.len()),
+                &content[start..],
+            ),
+            None => ("200 OK", String::new(), content),
+        };
+        let mut response = format!(
+            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nETag: {ETAG}\r\n{extra}Connection: close\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    fn agent() -> ureq::Agent {
+        ureq::Agent::config_builder().proxy(None).build().into()
+    }
+
+    fn sha256(content: &[u8]) -> String {
+        to_hex(&Sha256::digest(content))
+    }
+
+    /// 写入已下载 `downloaded` 字节的 `{hash}.part` 和对应的 `{hash}.part.json`
+    fn write_partial(target: &Path, url: &str, hash: &str, downloaded: &[u8], etag: &str) {
+        let (part, meta) = part_paths(target);
+        fs::write(part, downloaded).unwrap();
+        let partial = PartialDownload {
+            url: url.to_string(),
+            hash: hash.to_string(),
+            size: Some(CONTENT.len() as u64),
+            etag: Some(etag.to_string()),
+            last_modified: None,
+        };
+        fs::write(meta, serde_json::to_vec(&partial).unwrap()).unwrap();
+    }
+
+    const CONTENT: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    #[test]
+    fn parses_content_range() {
+        assert_eq!(content_range("bytes 100-199/1000"), Some((100, Some(1000))));
+        assert_eq!(content_range("bytes 100-199/*"), Some((100, None)));
+        assert_eq!(content_range("bytes */1000"), None);
+        assert_eq!(content_range("items 0-1/2"), None);
+    }
+
+    #[test]
+    fn downloads_and_verifies() {
+        let server = Server::start(CONTENT);
+        let dir = TempDir::new("download");
+        let hash = sha256(CONTENT);
+        let target = dir.path().join(&hash);
+        let progress = Mutex::new(Vec::new());
+        let result = download_file(&agent(), &server.url, &target, &hash, |done| {
+            progress.lock().unwrap().push(done)
+        });
+        assert!(result.is_ok());
+        assert_eq!(fs::read(&target).unwrap(), CONTENT);
+        assert_eq!(progress.lock().unwrap().last(), Some(&1.0));
+        let (part, meta) = part_paths(&target);
+        assert!(!part.exists() && !meta.exists());
+        let requests = server.requests();
+        assert!(!requests[0].contains("range:"), "{requests:?}");
+    }
+
+    #[test]
+    fn resumes_with_range_and_if_range() {
+        let server = Server::start(CONTENT);
+        let dir = TempDir::new("download");
+        let hash = sha256(CONTENT);
+        let target = dir.path().join(&hash);
+        write_partial(&target, &server.url, &hash, &CONTENT[..10], ETAG);
+
+        assert!(download_file(&agent(), &server.url, &target, &hash, |_| {}).is_ok());
+        assert_eq!(fs::read(&target).unwrap(), CONTENT);
+        let requests = server.requests();
+        assert!(requests[0].contains("range: bytes=10-"), "{requests:?}");
+        assert!(requests[0].contains(&format!("if-range: {ETAG}")), "{requests:?}");
+    }
+
+    #[test]
+    fn restarts_when_file_changed_on_server() {
+        let server = Server::start(CONTENT);
+        let dir = TempDir::new("download");
+        let hash = sha256(CONTENT);
+        let target = dir.path().join(&hash);
+        // 服务器上的 ETag 已变化，If-Range 不匹配时返回完整的文件
+        write_partial(&target, &server.url, &hash, b"old content", "\"v1\"");
+
+        assert!(download_file(&agent(), &server.url, &target, &hash, |_| {}).is_ok());
+        assert_eq!(fs::read(&target).unwrap(), CONTENT);
+    }
+
+    #[test]
+    fn rejects_hash_mismatch() {
+        let server = Server::start(CONTENT);
+        let dir = TempDir::new("download");
+        let hash = sha256(b"something else");
+        let target = dir.path().join(&hash);
+
+        let result = download_file(&agent(), &server.url, &target, &hash, |_| {});
+        assert!(matches!(result, Err(Error::Fatal(FailReason::Integrity, _))));
+        let (part, meta) = part_paths(&target);
+        assert!(!target.exists() && !part.exists() && !meta.exists());
+    }
+
+    #[test]
+    fn retries_resumed_download_with_hash_mismatch() {
+        let server = Server::start(CONTENT);
+        let dir = TempDir::new("download");
+        let hash = sha256(CONTENT);
+        let target = dir.path().join(&hash);
+        // 续传的前半部分来自其他内容，拼接后摘要不一致，应删除后从头下载
+        write_partial(&target, &server.url, &hash, b"XXXXXXXXXX", ETAG);
+
+        let result = download_file(&agent(), &server.url, &target, &hash, |_| {});
+        assert!(matches!(result, Err(Error::Transient(_))));
+        let (part, meta) = part_paths(&target);
+        assert!(!target.exists() && !part.exists() && !meta.exists());
+
+        assert!(download_file(&agent(), &server.url, &target, &hash, |_| {}).is_ok());
+        assert_eq!(fs::read(&target).unwrap(), CONTENT);
+    }
+
+    #[test]
+    fn rejects_unknown_hash() {
+        let server = Server::start(CONTENT);
+        let dir = TempDir::new("download");
+        let target = dir.path().join("unknown");
+        let result = download_file(&agent(), &server.url, &target, "unknown", |_| {});
+        assert!(matches!(result, Err(Error::Fatal(FailReason::Integrity, _))));
+        assert!(!target.exists());
+        assert!(!matches_hash(&target, "unknown"));
+    }
+}
@@ -0,0 +1,175 @@
+//! 分阶段发布
+//!
+//! 每台机器根据版本号和机器标识的 SHA-256 得到一个位置（0 到 100），位置小于发布比例时才安装更新，
+//! 因此同一台机器对同一个版本总是得到相同的结果，比例调大后已在范围内的机器仍在范围内；
+//! 不同版本的位置互不相关，不会总是同一批机器先更新。
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    logging::{Log, Logger},
+    update::{callbacks::FailReason, hash::to_hex},
+};
+
+/// 没有系统机器标识时保存生成的标识的文件，位于更新程序所在目录
+const FALLBACK_ID_FILE: &str = ".updater_machine_id";
+
+/// 检查更新配置中的发布比例，不在 0 到 100 之间时更新配置无效
+pub(super) fn check_percentage(percentage: Option<f64>) -> Result<(), FailReason> {
+    match percentage {
+        Some(percentage) if !(0.0..=100.0).contains(&percentage) => {
+            Log::error(format!("分阶段发布比例必须在 0 到 100 之间: {percentage}").as_str());
+            Err(FailReason::Manifest)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 本机是否在版本 `version` 的发布比例 `percentage`（0 到 100）之内，没有版本号时只按机器标识计算
+pub(super) fn in_rollout(percentage: f64, version: Option<&str>) -> bool {
+    if percentage >= 100.0 {
+        return true;
+    }
+    let position = position(&machine_id(), version.unwrap_or_default());
+    let included = position < percentage;
+    Log::info(
+        format!(
+            "分阶段发布: 发布比例 {percentage}%，本机位置 {position:.2}，{}",
+            if included {
+                "在范围内"
+            } else {
+                "不在范围内"
+            }
+        )
+        .as_str(),
+    );
+    included
+}
+
+/// 机器标识在版本 `version` 中 0 到 100 之间的位置，精度为 0.01
+fn position(machine_id: &str, version: &str) -> f64 {
+    let digest = Sha256::digest(format!("{}\n{}", version.trim(), machine_id.trim()).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(bytes) % 10_000) as f64 / 100.0
+}
+
+/// 本机的稳定标识，依次使用环境变量 `update_machine_id`、系统的机器标识和更新程序生成并保存的标识
+fn machine_id() -> String {
+    if let Ok(id) = env::var("update_machine_id") {
+        return id;
+    }
+    if let Some(id) = system_machine_id() {
+        return id;
+    }
+    let path = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(FALLBACK_ID_FILE)))
+        .unwrap_or_else(|| PathBuf::from(FALLBACK_ID_FILE));
+    if let Ok(id) = fs::read_to_string(&path) {
+        if !id.trim().is_empty() {
+            return id;
+        }
+    }
+    Log::warn("无法读取系统的机器标识，生成新的标识");
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let id = to_hex(&Sha256::digest(format!("{nanos}-{}", process::id()).as_bytes()));
+    if let Err(e) = fs::write(&path, &id) {
+        Log::warn(format!("保存机器标识失败: {e}").as_str());
+    }
+    id
+}
+
+#[cfg(target_os = "linux")]
+fn system_machine_id() -> Option<String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+}
+
+#[cfg(target_os = "macos")]
+fn system_machine_id() -> Option<String> {
+    let output = process::Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("IOPlatformUUID"))
+        .and_then(|line| line.split('"').nth(3))
+        .map(str::to_string)
+}
+
+#[cfg(target_os = "windows")]
+fn system_machine_id() -> Option<String> {
+    let output = process::Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SOFTWARE\Microsoft\Cryptography",
+            "/v",
+            "MachineGuid",
+        ])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("MachineGuid"))
+        .and_then(|line| line.split_whitespace().last())
+        .map(str::to_string)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn system_machine_id() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_is_stable_and_in_range() {
+        let position = position("machine-a", "1.2.0");
+        assert_eq!(position, super::position("machine-a", "1.2.0"));
+        assert_eq!(position, super::position(" machine-a\n", "1.2.0 "));
+        assert!((0.0..100.0).contains(&position));
+    }
+
+    #[test]
+    fn position_depends_on_version() {
+        // 换版本后位置重新分布，不会总是同一批机器先更新
+        let changed = (0..20)
+            .map(|i| format!("machine-{i}"))
+            .filter(|id| position(id, "1.2.0") != position(id, "1.3.0"))
+            .count();
+        assert!(changed > 15, "{changed}");
+    }
+
+    #[test]
+    fn rejects_percentage_out_of_range() {
+        assert_eq!(check_percentage(None), Ok(()));
+        assert_eq!(check_percentage(Some(0.0)), Ok(()));
+        assert_eq!(check_percentage(Some(100.0)), Ok(()));
+        assert_eq!(check_percentage(Some(-1.0)), Err(FailReason::Manifest));
+        assert_eq!(check_percentage(Some(150.0)), Err(FailReason::Manifest));
+        assert_eq!(check_percentage(Some(f64::NAN)), Err(FailReason::Manifest));
+    }
+
+    #[test]
+    fn full_rollout_includes_everyone() {
+        assert!(in_rollout(100.0, Some("1.2.0")));
+        assert!(in_rollout(150.0, None));
+    }
+}
@@ -0,0 +1,125 @@
+//! 下载来源
+//!
+//! 来源可以是 http(s) 地址，也可以是本地目录（`file://` 开头或绝对路径），例如局域网共享或离线介质。
+//! 本地目录的结构与更新服务器相同。
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    logging::{Log, Logger},
+    update::{callbacks::FailReason, hash::HashAlgorithm, options::DownloadOptions},
+};
+
+use super::{download_file, fetch, join_url, with_retry};
+
+#[derive(Clone, Debug)]
+pub(super) enum Source {
+    Http(String),
+    Dir(PathBuf),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Http(url) => f.write_str(url),
+            Source::Dir(dir) => write!(f, "{}", dir.to_string_lossy()),
+        }
+    }
+}
+
+impl Source {
+    pub(super) fn parse(location: &str) -> Option<Source> {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            Some(Source::Http(location.trim_end_matches('/').to_string()))
+        } else if let Some(path) = location.strip_prefix("file://") {
+            Some(Source::Dir(PathBuf::from(path)))
+        } else if Path::new(location).is_absolute() {
+            Some(Source::Dir(PathBuf::from(location)))
+        } else {
+            None
+        }
+    }
+
+    /// 来源下的子目录
+    pub(super) fn join(&self, name: &str) -> Source {
+        match self {
+            Source::Http(url) => Source::Http(join_url(url, name)),
+            Source::Dir(dir) => Source::Dir(dir.join(name)),
+        }
+    }
+
+    /// 来源中名为 `name` 的文件的地址或路径
+    pub(super) fn location(&self, name: &str) -> String {
+        match self {
+            Source::Http(url) => join_url(url, name),
+            Source::Dir(dir) => dir.join(name).to_string_lossy().to_string(),
+        }
+    }
+
+    /// 读取较小的文件，例如更新配置
+    pub(super) fn fetch(
+        &self,
+        agent: &ureq::Agent,
+        options: &DownloadOptions,
+        name: &str,
+    ) -> Result<Vec<u8>, FailReason> {
+        let location = self.location(name);
+        match self {
+            Source::Http(_) => with_retry(options, &location, || fetch(agent, &location)),
+            Source::Dir(dir) => fs::read(dir.join(name)).map_err(|e| {
+                Log::error(format!("读取 {location} 失败: {e}").as_str());
+                FailReason::Download
+            }),
+        }
+    }
+
+    /// 下载名为 `name` 的文件到 `target`，摘要与 `hash` 不一致时返回 `FailReason::Integrity`
+    pub(super) fn fetch_file(
+        &self,
+        agent: &ureq::Agent,
+        options: &DownloadOptions,
+        name: &str,
+        target: &Path,
+        hash: &str,
+        on_progress: impl Fn(f64),
+    ) -> Result<(), FailReason> {
+        let location = self.location(name);
+        match self {
+            Source::Http(_) => with_retry(options, &location, || {
+                download_file(agent, &location, target, hash, &on_progress)
+            }),
+            Source::Dir(dir) => copy_file(&dir.join(name), target, hash).map_err(|e| {
+                Log::error(format!("复制 {location} 失败: {e}").as_str());
+                if e.kind() == io::ErrorKind::InvalidData {
+                    FailReason::Integrity
+                } else {
+                    FailReason::Download
+                }
+            }),
+        }
+    }
+}
+
+/// 从本地目录复制文件，校验摘要后再改名；无法识别摘要算法时同样视为不一致
+fn copy_file(from: &Path, target: &Path, hash: &str) -> io::Result<()> {
+    let Some(algorithm) = HashAlgorithm::detect(hash) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("无法识别摘要算法: {hash}"),
+        ));
+    };
+    let part = target.with_extension("part");
+    fs::copy(from, &part)?;
+    let actual = algorithm.hash_file(&part)?;
+    if !actual.eq_ignore_ascii_case(hash) {
+        let _ = fs::remove_file(&part);
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("摘要不一致: 期望 {hash}, 实际 {actual}"),
+        ));
+    }
+    fs::rename(&part, target)
+}
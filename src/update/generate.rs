@@ -0,0 +1,166 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::logging::{Log, Logger};
+
+use super::{
+    hash::HashAlgorithm,
+    state::{FileHashAndPath, UpdateConfigJson},
+    verify::normalize,
+};
+
+/// 生成更新目录的结果
+#[derive(Debug)]
+pub struct GenerateReport {
+    /// 写入更新目录的更新配置
+    pub config: UpdateConfigJson,
+    /// 新版本中已删除的文件
+    ///
+    /// 更新配置无法表达删除，这些文件不会写入更新配置，安装更新后仍保留在安装目录中，需要时由新版本自行清理
+    pub removed: Vec<String>,
+    /// 更新配置的路径
+    pub config_path: PathBuf,
+}
+
+/// 对比新旧两个解包后的 electron 版本，生成 `update()` 所需的更新目录
+///
+/// 新增和变更的文件以摘要命名复制到 `out_dir`，并在其中写入名为 `config_file_name` 的更新配置；
+/// 内容相同的文件只复制一份。新版本中删除的文件只在 [`GenerateReport::removed`] 中返回，安装时不会删除
+pub fn generate(
+    old_dir: &Path,
+    new_dir: &Path,
+    out_dir: &Path,
+    config_file_name: &str,
+    algorithm: HashAlgorithm,
+) -> io::Result<GenerateReport> {
+    let old_files = list_files(old_dir)?;
+    let new_files = list_files(new_dir)?;
+    fs::create_dir_all(out_dir)?;
+
+    let mut config = UpdateConfigJson {
+        version: None,
+        added: Vec::new(),
+        changed: Vec::new(),
+        rollout: None,
+        release_notes: BTreeMap::new(),
+    };
+    let mut written = HashSet::new();
+    for (relative, new_path) in new_files.iter() {
+        let hash = algorithm.hash_file(new_path)?;
+        let target = match old_files.get(relative) {
+            None => &mut config.added,
+            Some(old_path) if algorithm.hash_file(old_path)? != hash => &mut config.changed,
+            Some(_) => continue,
+        };
+        if written.insert(hash.clone()) {
+            fs::copy(new_path, out_dir.join(&hash))?;
+        }
+        Log::info(format!("{relative} -> {hash}").as_str());
+        target.push(FileHashAndPath {
+            filePath: relative.clone(),
+            hash,
+        });
+    }
+    let removed: Vec<String> = old_files
+        .keys()
+        .filter(|relative| !new_files.contains_key(*relative))
+        .cloned()
+        .collect();
+
+    let config_path = out_dir.join(config_file_name);
+    let json = serde_json::to_string_pretty(&config).map_err(io::Error::other)?;
+    fs::write(&config_path, json)?;
+    Ok(GenerateReport {
+        config,
+        removed,
+        config_path,
+    })
+}
+
+/// 列出目录下的所有文件，键为 `/` 分隔的相对路径
+fn list_files(root: &Path) -> io::Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![root.to_owned()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                dirs.push(path);
+            } else if file_type.is_symlink() && path.is_dir() {
+                // 不跟随指向目录的符号链接，避免重复或循环（如 macOS 应用包中的 Versions/Current）
+                Log::warn(format!("跳过指向目录的符号链接 {}", path.to_string_lossy()).as_str());
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                files.insert(normalize(&relative.to_string_lossy()), path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::update::test_support::TempDir;
+
+    #[test]
+    fn lists_added_changed_and_removed_files() {
+        let root = TempDir::new("generate");
+        root.write("old/electron", b"v1");
+        root.write("old/resources/app.asar", b"same");
+        root.write("old/resources/removed.pak", b"gone");
+        root.write("new/electron", b"v2");
+        root.write("new/resources/app.asar", b"same");
+        root.write("new/locales/a.pak", b"locale");
+        root.write("new/locales/b.pak", b"locale");
+        let out = root.path().join("out");
+
+        let report = generate(
+            &root.path().join("old"),
+            &root.path().join("new"),
+            &out,
+            "update-config.json",
+            HashAlgorithm::Sha256,
+        )
+        .unwrap();
+
+        let paths = |files: &[FileHashAndPath]| -> Vec<String> {
+            files.iter().map(|file| file.filePath.clone()).collect()
+        };
+        assert_eq!(paths(&report.config.added), ["locales/a.pak", "locales/b.pak"]);
+        assert_eq!(paths(&report.config.changed), ["electron"]);
+        assert_eq!(report.removed, ["resources/removed.pak"]);
+        assert_eq!(report.config_path, out.join("update-config.json"));
+
+        // 内容相同的文件只复制一份
+        let added = &report.config.added;
+        assert_eq!(added[0].hash, added[1].hash);
+        let mut copied: Vec<String> = fs::read_dir(&out)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        copied.sort();
+        let mut expected = vec![
+            added[0].hash.clone(),
+            report.config.changed[0].hash.clone(),
+            "update-config.json".to_string(),
+        ];
+        expected.sort();
+        assert_eq!(copied, expected);
+        assert_eq!(fs::read(out.join(&added[0].hash)).unwrap(), b"locale");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_directory_symlinks() {
+        let root = TempDir::new("generate");
+        root.write("app/Versions/A/electron", b"v1");
+        std::os::unix::fs::symlink("A", root.path().join("app/Versions/Current")).unwrap();
+        let files = list_files(&root.path().join("app")).unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), ["Versions/A/electron"]);
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Read},
+    path::Path,
+    str::FromStr,
+};
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// 更新配置中 `hash` 字段使用的摘要算法，按十六进制字符串的长度区分
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// 根据已有的摘要推断算法，不是十六进制摘要时返回 `None`
+    pub fn detect(hash: &str) -> Option<Self> {
+        if !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        match hash.len() {
+            32 => Some(HashAlgorithm::Md5),
+            40 => Some(HashAlgorithm::Sha1),
+            64 => Some(HashAlgorithm::Sha256),
+            128 => Some(HashAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    /// 计算文件的摘要，返回小写十六进制字符串
+    pub fn hash_file<P: AsRef<Path>>(self, path: P) -> io::Result<String> {
+        let file = fs::File::open(path)?;
+        match self {
+            HashAlgorithm::Md5 => digest_reader::<Md5, _>(file),
+            HashAlgorithm::Sha1 => digest_reader::<Sha1, _>(file),
+            HashAlgorithm::Sha256 => digest_reader::<Sha256, _>(file),
+            HashAlgorithm::Sha512 => digest_reader::<Sha512, _>(file),
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "md5" => Ok(HashAlgorithm::Md5),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            _ => Err(format!("不支持的摘要算法: {s}")),
+        }
+    }
+}
+
+fn digest_reader<D: Digest, R: Read>(mut reader: R) -> io::Result<String> {
+    let mut hasher = D::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        })
+}
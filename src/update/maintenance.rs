@@ -0,0 +1,211 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json;
+
+use crate::logging::{Log, Logger};
+
+use super::{
+    callbacks::FailReason,
+    options::TaskOptions,
+    runner::callback,
+    state::{RunningConfig, RunningState, RUNNING_STATUS_FILE},
+    verify::{read_manifest, verify_install, VerifyReport},
+};
+
+/// 读取运行状态文件，文件不存在时返回 `Ok(None)`
+pub fn load_running_config() -> Result<Option<RunningConfig>, FailReason> {
+    let running_config_path = Path::new(RUNNING_STATUS_FILE);
+    if !running_config_path.exists() {
+        return Ok(None);
+    }
+    match serde_json::from_slice::<RunningConfig>(
+        &fs::read(running_config_path).unwrap_or_default(),
+    ) {
+        Ok(config) => Ok(Some(config)),
+        Err(e) => {
+            Log::error("读取运行配置失败：");
+            Log::error(e.to_string().as_str());
+            Err(FailReason::RunningState)
+        }
+    }
+}
+
+/// 回滚未完成或失败的更新，返回是否执行了回滚
+pub fn rollback() -> Result<bool, FailReason> {
+    let Some(mut config) = load_running_config()? else {
+        return Ok(false);
+    };
+    if matches!(config.status, RunningState::Nothing | RunningState::Finish) {
+        return Ok(false);
+    }
+    let mut running_config_file = match fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(RUNNING_STATUS_FILE)
+    {
+        Ok(file) => file,
+        Err(e) => {
+            Log::error("打开运行状态文件失败");
+            Log::error(e.to_string().as_str());
+            return Err(FailReason::RunningState);
+        }
+    };
+    Log::info("回滚未完成的更新");
+    callback(&mut running_config_file, &mut config);
+    Ok(true)
+}
+
+/// 检查暂存的更新是否完整，返回更新配置中引用但不存在的文件
+pub fn verify_staging(options: &TaskOptions) -> Result<Vec<PathBuf>, FailReason> {
+    let update_temp_path = staging_dir(options)?;
+    let update_config_path = update_temp_path.join(options.update_config_file_name());
+    let config = read_manifest(&update_config_path)?;
+    Ok(config
+        .added
+        .iter()
+        .chain(config.changed.iter())
+        .map(|item| update_temp_path.join(&item.hash))
+        .filter(|path| !path.is_file())
+        .collect())
+}
+
+/// 按更新配置校验安装目录，`manifest` 未指定时使用更新目录中的更新配置
+///
+/// `full` 为 `true` 时同时报告安装目录中未被记录的文件，更新目录和更新程序自身除外
+pub fn verify_installed(
+    options: &TaskOptions,
+    manifest: Option<&Path>,
+    full: bool,
+) -> Result<VerifyReport, FailReason> {
+    let install_dir = install_dir(options)?;
+    let staging_dir = staging_dir(options)?;
+    let manifest_path = match manifest {
+        Some(path) => path.to_owned(),
+        None => staging_dir.join(options.update_config_file_name()),
+    };
+    let config = read_manifest(&manifest_path)?;
+    let mut ignore = vec![staging_dir];
+    if let Ok(current_exe) = std::env::current_exe() {
+        ignore.push(current_exe);
+    }
+    Ok(verify_install(&install_dir, &config, full, &ignore))
+}
+
+/// 清理已完成更新遗留的文件，返回被删除的路径
+///
+/// 存在未完成或失败的更新时不做任何清理，需要先 `resume` 或 `rollback`。只有运行状态为
+/// `Finish` 时才删除整个 `update_temp` 目录；没有运行状态文件或状态为 `Nothing` 时更新目录中可能是
+/// 尚未安装（例如用户选择了“稍后”）的更新，只删除旧版本的备份和旧的更新程序
+pub fn clean(options: &TaskOptions) -> Result<Vec<PathBuf>, FailReason> {
+    let running_config = load_running_config()?;
+    if let Some(config) = &running_config {
+        if !matches!(config.status, RunningState::Nothing | RunningState::Finish) {
+            Log::error("存在未完成的更新，已跳过清理");
+            return Err(FailReason::RunningState);
+        }
+    }
+    let finished = running_config
+        .as_ref()
+        .is_some_and(|config| config.status == RunningState::Finish);
+    let mut removed = Vec::new();
+    if let Ok(update_temp_path) = staging_dir(options) {
+        match update_temp_path.file_name().and_then(|name| name.to_str()) {
+            Some("update_temp") if finished && update_temp_path.exists() => {
+                remove(&update_temp_path, &mut removed);
+            }
+            _ => {
+                remove(&update_temp_path.join(".update_temp_path_old_version"), &mut removed);
+                remove(&update_temp_path.join("updater_old"), &mut removed);
+            }
+        }
+    }
+    if running_config.is_some() {
+        remove(Path::new(RUNNING_STATUS_FILE), &mut removed);
+    }
+    Ok(removed)
+}
+
+fn staging_dir(options: &TaskOptions) -> Result<PathBuf, FailReason> {
+    if let Some(path) = options
+        .update_temp_path
+        .as_ref()
+        .filter(|p| p.is_absolute())
+    {
+        return Ok(path.clone());
+    }
+    if options.exe_path.is_none() {
+        if let Some(config) = load_running_config()? {
+            if !config.update_temp_path.is_empty() {
+                return Ok(PathBuf::from(config.update_temp_path));
+            }
+        }
+    }
+    Ok(options.update_temp_path(&install_dir(options)?))
+}
+
+/// 确定 electron 的安装目录：优先使用参数中的 `exe_path`，否则使用运行状态文件中记录的
+pub fn install_dir(options: &TaskOptions) -> Result<PathBuf, FailReason> {
+    let exe_path = match &options.exe_path {
+        Some(path) => path.clone(),
+        None => match load_running_config()? {
+            Some(config) if !config.exe_path.is_empty() => PathBuf::from(config.exe_path),
+            _ => {
+                Log::error("缺少 exe_path，无法确定安装目录");
+                return Err(FailReason::InstallDir);
+            }
+        },
+    };
+    match exe_path.parent() {
+        Some(root) => Ok(root.to_owned()),
+        None => {
+            Log::error("无法获取根目录");
+            Err(FailReason::InstallDir)
+        }
+    }
+}
+
+fn remove(path: &Path, removed: &mut Vec<PathBuf>) {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else if path.exists() {
+        fs::remove_file(path)
+    } else {
+        return;
+    };
+    match result {
+        Ok(_) => removed.push(path.to_owned()),
+        Err(e) => {
+            Log::error("清理文件失败");
+            Log::error(path.to_string_lossy().as_ref());
+            Log::error(e.to_string().as_str());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::update::test_support::TempDir;
+
+    #[test]
+    fn clean_keeps_pending_update() {
+        // 工作目录中没有 .running_status，更新目录中是尚未安装的更新
+        assert!(!Path::new(RUNNING_STATUS_FILE).exists());
+        let dir = TempDir::new("clean");
+        let staged = dir.write("update_temp/update-config.json", b"{}");
+        dir.write("update_temp/updater_old", b"old");
+        dir.write("update_temp/.update_temp_path_old_version/electron", b"old");
+        let options = TaskOptions {
+            update_temp_path: Some(dir.path().join("update_temp")),
+            ..Default::default()
+        };
+
+        let removed = clean(&options).unwrap();
+        assert_eq!(removed.len(), 2, "{removed:?}");
+        assert!(staged.exists());
+        assert!(!dir.path().join("update_temp/updater_old").exists());
+    }
+}
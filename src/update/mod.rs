@@ -1,9 +1,29 @@
 mod callbacks;
+#[cfg(feature = "download")]
+pub mod download;
+pub mod generate;
+mod hash;
+pub mod maintenance;
+mod notes;
 mod ops;
+mod options;
+mod progress;
 mod runner;
 mod state;
+pub mod status;
 pub mod sysinfo;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod verify;
 
-pub use callbacks::UpdateUi;
-pub use runner::run_task;
-pub use state::{RunningConfig, RunningState};
+pub use callbacks::{FailReason, UpdatePhase, UpdateSummary, UpdateUi};
+pub use hash::HashAlgorithm;
+pub use ops::request_cancel;
+pub use options::{
+    parse_color, Appearance, DownloadFormat, DownloadOptions, PaletteOptions, RelaunchOptions,
+    TaskOptions, Timeouts, UiOptions,
+};
+pub use runner::{retry_task_with, run_task, run_task_with};
+pub use state::{
+    FileHashAndPath, RunningConfig, RunningState, UpdateConfigJson, RUNNING_STATUS_FILE,
+};
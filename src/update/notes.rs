@@ -0,0 +1,42 @@
+//! 更新说明
+//!
+//! 更新说明为 Markdown，可以写在更新配置的 `releaseNotes` 中（键为 `zh-CN`、`en-US` 这样的语言标记，
+//! `default` 用于其他语言），也可以放在更新目录中的 `release-notes.zh-CN.md`、`release-notes.md`。
+//! 依次查找更新配置和旁路文件中对应语言的说明，再查找两者中的 `default`。
+
+use std::{fs, path::Path};
+
+use crate::i18n::Locale;
+
+use super::state::UpdateConfigJson;
+
+/// 更新配置中不区分语言的说明使用的键
+const DEFAULT_KEY: &str = "default";
+
+/// 找到 `locale` 对应的更新说明，都没有时为空
+pub(crate) fn release_notes(
+    config: &UpdateConfigJson,
+    update_temp_path: &Path,
+    locale: Locale,
+) -> Option<String> {
+    let localized = config
+        .release_notes
+        .get(locale.tag())
+        .or_else(|| {
+            config
+                .release_notes
+                .iter()
+                .find(|(tag, _)| Locale::parse(tag) == Some(locale))
+                .map(|(_, notes)| notes)
+        })
+        .cloned()
+        .or_else(|| sidecar(update_temp_path, &format!("release-notes.{}.md", locale.tag())));
+    localized
+        .or_else(|| config.release_notes.get(DEFAULT_KEY).cloned())
+        .or_else(|| sidecar(update_temp_path, "release-notes.md"))
+        .filter(|notes| !notes.trim().is_empty())
+}
+
+fn sidecar(update_temp_path: &Path, file_name: &str) -> Option<String> {
+    fs::read_to_string(update_temp_path.join(file_name)).ok()
+}
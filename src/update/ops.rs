@@ -1,6 +1,6 @@
 use std::{
     env, fs,
-    io::{Seek, Write},
+    io::{self, Read, Seek, Write},
     path::Path,
     sync::atomic::{AtomicBool, Ordering},
     time::Duration,
@@ -12,11 +12,16 @@ use crate::logging::{Log, Logger};
 
 use super::{
     callbacks::UpdateUi,
+    progress::ByteProgress,
     state::{RunningConfig, RunningState, UpdateConfigJson},
 };
 
+/// 复制文件时每次读写的大小
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
 static NEED_UPDATE_MYSELF: AtomicBool = AtomicBool::new(false);
 static UPDATE_MYSELF_NOW: AtomicBool = AtomicBool::new(false);
+static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
 
 fn set_need_update_myself(value: bool) {
     NEED_UPDATE_MYSELF.store(value, Ordering::SeqCst);
@@ -35,6 +40,19 @@ pub(crate) fn mark_update_myself_now() -> bool {
     UPDATE_MYSELF_NOW.load(Ordering::SeqCst)
 }
 
+/// 请求取消当前的更新，更新程序会在处理下一个文件前（或复制大文件的过程中）停止并回滚
+pub fn request_cancel() {
+    CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+pub(crate) fn cancel_requested() -> bool {
+    CANCEL_REQUESTED.load(Ordering::SeqCst)
+}
+
+pub(crate) fn reset_cancel() {
+    CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+}
+
 pub(crate) fn check_permission<P: AsRef<Path>>(
     config: &UpdateConfigJson,
     path: P,
@@ -88,7 +106,10 @@ pub(crate) fn check_permission<P: AsRef<Path>>(
             }
         };
         let check = {
-            if !from_path.is_file() {
+            if cancel_requested() {
+                Log::info("更新已取消");
+                false
+            } else if !from_path.is_file() {
                 Log::error("缺少迁移的目标文件:");
                 Log::error(from_path.to_string_lossy().as_ref());
                 false
@@ -147,6 +168,15 @@ pub(crate) fn copy_file<P: AsRef<Path>>(
     let total_file = (config.added.len() + config.changed.len()) as f64;
     Log::info("总共需要迁移得文件为");
     Log::info(total_file.to_string().as_str());
+    let total_bytes: u64 = config
+        .added
+        .iter()
+        .chain(config.changed.iter())
+        .filter_map(|item| fs::metadata(update_temp_path.as_ref().join(&item.hash)).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    Log::info(format!("总共需要迁移 {total_bytes} 字节").as_str());
+    let mut progress = ByteProgress::new(ui, total_bytes);
     let current_exe_path = match env::current_exe() {
         Ok(path) => path,
         Err(e) => {
@@ -156,6 +186,12 @@ pub(crate) fn copy_file<P: AsRef<Path>>(
         }
     };
     for item in config.added.iter().chain(config.changed.iter()) {
+        if cancel_requested() {
+            Log::info("更新已取消");
+            running_config.status = RunningState::Failed;
+            flush_config_file(running_config_file, running_config);
+            return false;
+        }
         hand_file_num += 1.0;
         Log::info(format!(" 当前迁移第{}个文件", hand_file_num as u32).as_str());
         let file_path = path.as_ref().join(&item.filePath);
@@ -165,8 +201,12 @@ pub(crate) fn copy_file<P: AsRef<Path>>(
         let from_path = Path::new(update_temp_path.as_ref()).join(&item.hash);
         Log::info("迁移的源文件:");
         Log::info(from_path.to_string_lossy().as_ref());
-        ui.on_progress(hand_file_num / total_file);
+        ui.on_file(hand_file_num as usize, total_file as usize, &file_path);
+        progress.report();
         if need_update_myself() && file_path == current_exe_path {
+            if let Ok(metadata) = fs::metadata(&from_path) {
+                progress.advance(metadata.len());
+            }
             std::thread::spawn(move || loop {
                 let update_myself_now = update_myself_now();
                 if update_myself_now {
@@ -179,8 +219,9 @@ pub(crate) fn copy_file<P: AsRef<Path>>(
                     };
                     let r = fs::rename(&file_path, parent.join("updater_old"));
                     Log::info(format!("delete {r:#?}").as_str());
-                    let r = fs::rename(from_path, file_path);
-                    Log::info(format!("rename {r:#?}").as_str());
+                    // 更新目录中相同内容的文件只有一份，可能还被其他条目使用，不能移走
+                    let r = fs::copy(from_path, file_path);
+                    Log::info(format!("copy {r:#?}").as_str());
                     break;
                 }
                 std::thread::sleep(Duration::from_millis(50));
@@ -188,9 +229,18 @@ pub(crate) fn copy_file<P: AsRef<Path>>(
             continue;
         }
 
-        if fs::copy(from_path, &file_path).is_err() {
+        if let Err(e) = copy_with_progress(&from_path, &file_path, |bytes| progress.advance(bytes))
+        {
             Log::error("复制源文件到对应路径错误");
             Log::error(file_path.to_string_lossy().as_ref());
+            Log::error(e.to_string().as_str());
+            // 复制了一半的文件不在 moved_path 中，回滚时不会被删除
+            if let Err(e) = fs::remove_file(&file_path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    Log::error("删除未复制完的文件失败");
+                    Log::error(e.to_string().as_str());
+                }
+            }
             running_config.status = RunningState::Failed;
             flush_config_file(running_config_file, running_config);
             return false;
@@ -204,6 +254,29 @@ pub(crate) fn copy_file<P: AsRef<Path>>(
     true
 }
 
+/// 与 `fs::copy` 一样复制内容和权限，每写入一块就把块的大小传给 `on_bytes`；收到取消请求时中断复制
+fn copy_with_progress(from: &Path, to: &Path, mut on_bytes: impl FnMut(u64)) -> io::Result<()> {
+    let mut reader = fs::File::open(from)?;
+    let permissions = reader.metadata()?.permissions();
+    let mut writer = fs::File::create(to)?;
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+    loop {
+        if cancel_requested() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "更新已取消"));
+        }
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buffer[..read])?;
+        on_bytes(read as u64);
+    }
+    writer.flush()?;
+    fs::set_permissions(to, permissions)
+}
+
 pub(crate) fn flush_config_file(
     running_config_file: &mut fs::File,
     running_config: &RunningConfig,
@@ -0,0 +1,144 @@
+//! 按字节计算的进度和剩余时间
+//!
+//! 速度取每个采样区间的平均速度再做指数平滑，避免单个小文件或磁盘缓存让估计剧烈跳动。
+
+use std::time::{Duration, Instant};
+
+use super::callbacks::UpdateUi;
+
+/// 两次通知之间的最短间隔
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+/// 开始后至少经过这段时间才估计剩余时间
+const WARMUP: Duration = Duration::from_secs(1);
+/// 指数平滑中新采样的权重
+const SMOOTHING: f64 = 0.2;
+
+pub(crate) struct ByteProgress<'a, U: UpdateUi> {
+    ui: &'a U,
+    total: u64,
+    done: u64,
+    started: Instant,
+    sampled_at: Instant,
+    sampled_done: u64,
+    /// 平滑后的速度（字节/秒）
+    rate: Option<f64>,
+}
+
+impl<'a, U: UpdateUi> ByteProgress<'a, U> {
+    pub(crate) fn new(ui: &'a U, total: u64) -> Self {
+        let now = Instant::now();
+        Self {
+            ui,
+            total,
+            done: 0,
+            started: now,
+            sampled_at: now,
+            sampled_done: 0,
+            rate: None,
+        }
+    }
+
+    /// 又处理了 `bytes` 字节，距上次通知不足 `REPORT_INTERVAL` 时只记录不通知
+    pub(crate) fn advance(&mut self, bytes: u64) {
+        self.done = (self.done + bytes).min(self.total);
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.sampled_at);
+        if elapsed < REPORT_INTERVAL && self.done < self.total {
+            return;
+        }
+        let sample = (self.done - self.sampled_done) as f64 / elapsed.as_secs_f64().max(1e-3);
+        self.rate = Some(match self.rate {
+            Some(rate) => rate + SMOOTHING * (sample - rate),
+            None => sample,
+        });
+        self.sampled_at = now;
+        self.sampled_done = self.done;
+        self.report();
+    }
+
+    /// 按当前状态通知 `ui`
+    pub(crate) fn report(&self) {
+        let fraction = if self.total == 0 {
+            1.0
+        } else {
+            self.done as f64 / self.total as f64
+        };
+        self.ui.on_progress(fraction);
+        self.ui.on_bytes(self.done, self.total, self.eta());
+    }
+
+    /// 剩余时间的估计，刚开始或速度为零时为空
+    fn eta(&self) -> Option<Duration> {
+        if self.done >= self.total {
+            return Some(Duration::ZERO);
+        }
+        if self.started.elapsed() < WARMUP {
+            return None;
+        }
+        self.rate
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| Duration::from_secs_f64((self.total - self.done) as f64 / rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, thread};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        progress: RefCell<Vec<f64>>,
+        bytes: RefCell<Vec<(u64, u64, Option<Duration>)>>,
+    }
+
+    impl UpdateUi for Recorder {
+        fn on_progress(&self, progress: f64) {
+            self.progress.borrow_mut().push(progress);
+        }
+        fn on_bytes(&self, done: u64, total: u64, eta: Option<Duration>) {
+            self.bytes.borrow_mut().push((done, total, eta));
+        }
+        fn on_quit(&self) {}
+    }
+
+    #[test]
+    fn throttles_until_finished() {
+        let ui = Recorder::default();
+        let mut progress = ByteProgress::new(&ui, 100);
+        progress.advance(10);
+        progress.advance(10);
+        assert!(ui.progress.borrow().is_empty());
+        progress.advance(80);
+        assert_eq!(*ui.progress.borrow(), vec![1.0]);
+        assert_eq!(*ui.bytes.borrow(), vec![(100, 100, Some(Duration::ZERO))]);
+    }
+
+    #[test]
+    fn clamps_to_total() {
+        let ui = Recorder::default();
+        let mut progress = ByteProgress::new(&ui, 100);
+        progress.advance(150);
+        assert_eq!(ui.bytes.borrow().last().map(|b| b.0), Some(100));
+    }
+
+    #[test]
+    fn empty_task_is_complete() {
+        let ui = Recorder::default();
+        ByteProgress::new(&ui, 0).report();
+        assert_eq!(*ui.progress.borrow(), vec![1.0]);
+    }
+
+    #[test]
+    fn no_eta_during_warmup() {
+        let ui = Recorder::default();
+        let mut progress = ByteProgress::new(&ui, 1000);
+        thread::sleep(REPORT_INTERVAL);
+        progress.advance(100);
+        let bytes = ui.bytes.borrow();
+        assert_eq!(bytes.len(), 1);
+        assert_eq!(bytes[0].0, 100);
+        assert_eq!(bytes[0].2, None);
+    }
+}
@@ -1,29 +1,41 @@
 use std::{
-    env, fs,
+    fs,
     path::{Path, PathBuf},
-    process, thread,
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
 };
 
 use serde_json;
 
 use crate::{
+    i18n::Locale,
+    ipc::{self, IpcUi},
     logging::{Log, Logger},
-    update::sysinfo::end_electron_main,
+    update::sysinfo::{end_electron_main_with, is_electron_running},
 };
 
 use super::{
-    callbacks::UpdateUi,
-    ops::{check_permission, copy_file, flush_config_file, mark_update_myself_now},
-    state::{RunningConfig, RunningState, UpdateConfigJson},
+    callbacks::{FailReason, UpdatePhase, UpdateSummary, UpdateUi},
+    maintenance,
+    notes::release_notes,
+    ops::{
+        cancel_requested, check_permission, copy_file, flush_config_file, mark_update_myself_now,
+        reset_cancel,
+    },
+    options::TaskOptions,
+    state::{RunningConfig, RunningState, UpdateConfigJson, RUNNING_STATUS_FILE},
 };
 
 fn update(
     ui: &impl UpdateUi,
+    options: &TaskOptions,
     exe_path_buf: PathBuf,
     skip_check: bool,
     mut running_config: RunningConfig,
     runnning_config_path: &Path,
 ) {
+    ui.on_phase(UpdatePhase::Preparing);
     let mut running_config_file = {
         if runnning_config_path.exists() {
             match fs::OpenOptions::new()
@@ -35,7 +47,7 @@ fn update(
                 Err(e) => {
                     Log::error("打开运行状态文件失败");
                     Log::error(e.to_string().as_str());
-                    ui.on_failed();
+                    ui.on_failed_with(FailReason::RunningState);
                     return;
                 }
             }
@@ -45,7 +57,7 @@ fn update(
                 Err(e) => {
                     Log::error("创建运行状态文件失败");
                     Log::error(e.to_string().as_str());
-                    ui.on_failed();
+                    ui.on_failed_with(FailReason::RunningState);
                     return;
                 }
             }
@@ -60,53 +72,69 @@ fn update(
         Some(path) => path,
         None => {
             Log::error("无法获取根目录");
-            ui.on_failed();
+            ui.on_failed_with(FailReason::InstallDir);
             return;
         }
     };
     Log::info("根目录: ");
     Log::info(path.to_string_lossy().as_ref());
-    let update_temp_path = match env::var("update_temp_path") {
-        Ok(path) if Path::new(&path).is_absolute() => Path::new(&path).to_owned(),
-        _ => Path::new(&path).join("update_temp"),
-    };
+    let update_temp_path = options.update_temp_path(path);
     Log::info("更新temp目录: ");
     Log::info(update_temp_path.to_string_lossy().as_ref());
 
-    let update_config_file_name = match env::var("update_config_file_name") {
-        Ok(name) => name,
-        _ => "update-config.json".to_string(),
-    };
+    let update_config_file_name = options.update_config_file_name();
     Log::info("配置update_config_file_name: ");
-    Log::info(update_config_file_name.as_str());
+    Log::info(update_config_file_name);
     Log::info("读取更新配置：");
     Log::info("读取更新配置路径：");
-    let update_config_path = update_temp_path.join(&update_config_file_name);
+    let update_config_path = update_temp_path.join(update_config_file_name);
     Log::info(update_config_path.to_string_lossy().as_ref());
     running_config.update_temp_path = update_temp_path.to_string_lossy().to_string();
     flush_config_file(&mut running_config_file, &running_config);
     let config: UpdateConfigJson =
         match serde_json::from_slice(&fs::read(&update_config_path).unwrap_or_default()) {
             Ok(config) => config,
-            _ => {
+            Err(e) => {
                 Log::error("读取更新配置失败：");
+                Log::error(e.to_string().as_str());
                 running_config.status = RunningState::Nothing;
                 flush_config_file(&mut running_config_file, &running_config);
-                ui.on_failed();
+                ui.on_failed_with(FailReason::Manifest);
                 return;
             }
         };
     Log::info("读取更新配置为：");
-    Log::info(format!("{config:#?}").as_str());
+    Log::debug(format!("{config:#?}").as_str());
+    let locale = Locale::detect(options.ui.locale.as_deref());
+    if let Some(notes) = release_notes(&config, &update_temp_path, locale) {
+        ui.on_release_notes(&notes);
+    }
+    if cancel_requested() {
+        // 还没有结束 electron，也没有改动安装目录，不需要回滚和重启
+        Log::info("更新已取消");
+        running_config.status = RunningState::Nothing;
+        flush_config_file(&mut running_config_file, &running_config);
+        ui.on_failed_with(FailReason::Cancelled);
+        return;
+    }
     Log::info("开始更新");
     Log::info("处理未关闭的electron进程");
-    end_electron_main(exe_path);
+    ui.on_phase(UpdatePhase::KillingElectron);
+    end_electron_main_with(exe_path, options.exe_pid, options.timeouts.kill_wait());
     if !skip_check {
+        ui.on_phase(UpdatePhase::BackingUp);
         if !check_permission(&config, path, update_temp_path.as_path(), &mut running_config) {
             running_config.status = RunningState::Nothing;
             flush_config_file(&mut running_config_file, &running_config);
+            if cancel_requested() {
+                // check_permission 已把移走的旧文件放回原处
+                Log::info("更新已取消，重启旧版本");
+                relaunch(ui, options, exe_path, "cancelled");
+                ui.on_failed_with(FailReason::Cancelled);
+                return;
+            }
             Log::error("检测权限不通过，更新结束");
-            ui.on_failed();
+            ui.on_failed_with(FailReason::Permission);
             return;
         };
         running_config.status = RunningState::Updating;
@@ -114,6 +142,7 @@ fn update(
     }
 
     Log::info("迁移文件");
+    ui.on_phase(UpdatePhase::Copying);
     if !copy_file(
         &config,
         &path,
@@ -122,16 +151,24 @@ fn update(
         &mut running_config,
         ui,
     ) {
+        ui.on_phase(UpdatePhase::RollingBack);
         callback(&mut running_config_file, &mut running_config);
-        ui.on_failed();
+        if cancel_requested() {
+            Log::info("更新已取消，已回滚，重启旧版本");
+            relaunch(ui, options, exe_path, "cancelled");
+            ui.on_failed_with(FailReason::Cancelled);
+        } else {
+            ui.on_failed_with(FailReason::Copy);
+        }
     } else {
         running_config.status = RunningState::Finish;
         flush_config_file(&mut running_config_file, &running_config);
         Log::info("迁移文件结束，更新完成");
         Log::info("清理更新文件");
+        ui.on_phase(UpdatePhase::Cleaning);
         let update_myself_now = mark_update_myself_now();
         Log::info(&format!("set UPDATE_MYSELF_NOW {update_myself_now}"));
-        std::thread::sleep(std::time::Duration::from_millis(500));
+        std::thread::sleep(options.timeouts.self_update_wait());
         match update_temp_path.file_name().and_then(|name| name.to_str()) {
             Some("update_temp") => {
                 if let Err(e) = fs::remove_dir_all(update_temp_path) {
@@ -144,28 +181,131 @@ fn update(
             }
         };
         Log::info("清理更新文件完成");
-        Log::info("重启程序");
-        let mut child = match process::Command::new(exe_path)
-            .env("updateCallback", "success")
-            .spawn()
-        {
-            Ok(child) => child,
-            Err(e) => {
-                Log::error("重启程序失败");
-                Log::error(e.to_string().as_str());
-                ui.on_failed();
-                return;
-            }
-        };
-        thread::spawn(move || {
-            let _ = child.wait();
-        });
+        if !relaunch(ui, options, exe_path, "success") {
+            ui.on_failed_with(FailReason::Relaunch);
+            return;
+        }
         Log::info("退出更新程序");
         ui.on_quit();
     }
 }
 
-fn callback(running_config_file: &mut fs::File, running_config: &mut RunningConfig) {
+/// 按配置重启electron，并通过环境变量 `updateCallback` 告知更新结果；配置为不重启时直接返回 `true`
+fn relaunch(
+    ui: &impl UpdateUi,
+    options: &TaskOptions,
+    exe_path: &Path,
+    update_callback: &str,
+) -> bool {
+    if !options.relaunch.enabled {
+        Log::info("已配置为不重启程序");
+        return true;
+    }
+    Log::info("重启程序");
+    ui.on_phase(UpdatePhase::Relaunching);
+    match process::Command::new(exe_path)
+        .args(&options.relaunch.args)
+        .env("updateCallback", update_callback)
+        .spawn()
+    {
+        Ok(mut child) => {
+            thread::spawn(move || {
+                let _ = child.wait();
+            });
+            true
+        }
+        Err(e) => {
+            Log::error("重启程序失败");
+            Log::error(e.to_string().as_str());
+            false
+        }
+    }
+}
+
+/// 询问用户是否立即安装，返回是否继续更新
+///
+/// 选择稍后时不改动更新目录和运行状态文件，electron 已退出时重启它，然后退出更新程序
+fn confirm(ui: &impl UpdateUi, options: &TaskOptions, exe_path: &Path) -> bool {
+    let Some(root) = exe_path.parent() else {
+        // 交给 update 报告
+        return true;
+    };
+    let update_temp_path = options.update_temp_path(root);
+    let manifest = fs::read(update_temp_path.join(options.update_config_file_name()))
+        .ok()
+        .and_then(|data| serde_json::from_slice::<UpdateConfigJson>(&data).ok());
+    let Some(config) = manifest else {
+        return true;
+    };
+    let size = config
+        .added
+        .iter()
+        .chain(config.changed.iter())
+        .filter_map(|file| fs::metadata(update_temp_path.join(&file.hash)).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    let locale = Locale::detect(options.ui.locale.as_deref());
+    let summary = UpdateSummary {
+        version: config.version.clone(),
+        size,
+        release_notes: release_notes(&config, &update_temp_path, locale),
+    };
+    Log::info(format!("等待用户确认更新: {summary:?}").as_str());
+    if ui.confirm(&summary) {
+        Log::info("用户选择立即安装");
+        return true;
+    }
+    Log::info("用户选择稍后更新，保留更新文件");
+    if !is_electron_running(exe_path, options.exe_pid) {
+        relaunch(ui, options, exe_path, "later");
+    }
+    ui.on_quit();
+    false
+}
+
+/// 应用更新前先从更新服务器下载更新，返回是否继续更新；失败或已是最新版本时已通知 `ui`
+#[cfg(feature = "download")]
+fn download(ui: &impl UpdateUi, options: &TaskOptions, exe_path: &Path) -> bool {
+    let Some(root) = exe_path.parent() else {
+        Log::error("无法获取根目录");
+        ui.on_failed_with(FailReason::InstallDir);
+        return false;
+    };
+    let update_temp_path = options.update_temp_path(root);
+    match super::download::download(
+        ui,
+        &options.download,
+        exe_path,
+        &update_temp_path,
+        options.update_config_file_name(),
+    ) {
+        Ok(Some(_)) => true,
+        Ok(None) => {
+            Log::info("没有需要安装的更新，退出更新程序");
+            ui.on_quit();
+            false
+        }
+        Err(_) if cancel_requested() => {
+            // 下载时还没有结束 electron，已下载的部分保留，下次继续下载
+            Log::info("下载已取消");
+            ui.on_failed_with(FailReason::Cancelled);
+            false
+        }
+        Err(reason) => {
+            ui.on_failed_with(reason);
+            false
+        }
+    }
+}
+
+#[cfg(not(feature = "download"))]
+fn download(ui: &impl UpdateUi, _options: &TaskOptions, _exe_path: &Path) -> bool {
+    Log::error("设置了更新服务器地址，但更新程序编译时未启用 download 特性");
+    ui.on_failed_with(FailReason::Config);
+    false
+}
+
+pub(crate) fn callback(running_config_file: &mut fs::File, running_config: &mut RunningConfig) {
     let update_temp_path = Path::new(&running_config.update_temp_path);
     running_config.status = RunningState::Failed;
     flush_config_file(running_config_file, running_config);
@@ -194,16 +334,131 @@ fn callback(running_config_file: &mut fs::File, running_config: &mut RunningConf
     flush_config_file(running_config_file, running_config);
 }
 
+/// 使用环境变量中的参数执行更新任务
 pub fn run_task(ui: impl UpdateUi) {
+    run_task_with(ui, TaskOptions::from_env());
+}
+
+/// 使用给定的参数执行更新任务，同一进程中已有更新任务在运行时只记录日志并直接返回
+pub fn run_task_with(ui: impl UpdateUi, options: TaskOptions) {
+    Log::setup_logging();
+    let Some(_guard) = RunnerGuard::acquire() else {
+        return;
+    };
+    reset_cancel();
+    match ipc::connect_from_env() {
+        Some(channel) => run(&IpcUi::new(ui, channel), &options),
+        None => run(&ui, &options),
+    }
+}
+
+/// 更新失败后重新更新
+///
+/// 先回滚未完成或失败的更新，再检查暂存的更新是否完整，最后按 `exe_path`（没有时使用运行状态文件中记录的）
+/// 重新执行更新；用户已经选择了重试，不再询问是否安装
+pub fn retry_task_with(ui: impl UpdateUi, mut options: TaskOptions) {
     Log::setup_logging();
+    let Some(_guard) = RunnerGuard::acquire() else {
+        return;
+    };
+    reset_cancel();
+    options.ui.confirm = false;
+    match ipc::connect_from_env() {
+        Some(channel) => retry(&IpcUi::new(ui, channel), options),
+        None => retry(&ui, options),
+    }
+}
+
+fn retry(ui: &impl UpdateUi, mut options: TaskOptions) {
+    Log::info("重试更新");
+    ui.on_phase(UpdatePhase::RollingBack);
+    match maintenance::rollback() {
+        Ok(true) => Log::info("已回滚上次未完成的更新"),
+        Ok(false) => Log::info("没有需要回滚的更新"),
+        Err(reason) => {
+            ui.on_failed_with(reason);
+            return;
+        }
+    }
+    if options.exe_path.is_none() {
+        match maintenance::load_running_config() {
+            Ok(Some(config)) if !config.exe_path.is_empty() => {
+                options.exe_path = Some(PathBuf::from(config.exe_path));
+            }
+            Ok(_) => {
+                Log::error("运行状态文件中没有记录 exe_path，无法重试");
+                ui.on_failed_with(FailReason::InstallDir);
+                return;
+            }
+            Err(reason) => {
+                ui.on_failed_with(reason);
+                return;
+            }
+        }
+    }
+    // 配置了更新服务器时重新下载会校验文件，这里只检查本地暂存的更新
+    if options.download.base_url.is_none() {
+        ui.on_phase(UpdatePhase::Preparing);
+        match maintenance::verify_staging(&options) {
+            Ok(missing) if missing.is_empty() => {}
+            Ok(missing) => {
+                for path in missing.iter() {
+                    Log::error(format!("暂存的更新缺少文件: {}", path.to_string_lossy()).as_str());
+                }
+                ui.on_failed_with(FailReason::Integrity);
+                return;
+            }
+            Err(reason) => {
+                ui.on_failed_with(reason);
+                return;
+            }
+        }
+    }
+    run(ui, &options);
+}
+
+/// 同一进程中同时只能有一个更新任务
+static RUNNER_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+struct RunnerGuard;
+
+impl RunnerGuard {
+    /// 已有更新任务在运行时返回 `None`
+    ///
+    /// 不通知 `ui`：失败事件会让界面显示失败、无界面运行时退出进程，而正在运行的更新任务仍需要它们
+    fn acquire() -> Option<RunnerGuard> {
+        if RUNNER_ACTIVE
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            Log::warn("已有更新任务在运行，忽略本次请求");
+            return None;
+        }
+        Some(RunnerGuard)
+    }
+}
+
+impl Drop for RunnerGuard {
+    fn drop(&mut self) {
+        RUNNER_ACTIVE.store(false, Ordering::Release);
+    }
+}
+
+fn run(ui: &impl UpdateUi, options: &TaskOptions) {
     Log::info("程序开始");
     Log::info("获取electron程序的执行目录,判断任务状态");
-    let running_config_path = Path::new(".running_status");
+    let running_config_path = Path::new(RUNNING_STATUS_FILE);
 
-    match env::var("exe_path") {
-        Ok(path) if Path::new(&path).is_absolute() => {
+    match &options.exe_path {
+        Some(path) if path.is_absolute() => {
             Log::info("执行更新程序");
-            let exe_path_buf = Path::new(&path).to_owned();
+            let exe_path_buf = path.clone();
+            if options.download.base_url.is_some() && !download(ui, options, &exe_path_buf) {
+                return;
+            }
+            if options.ui.confirm && !confirm(ui, options, &exe_path_buf) {
+                return;
+            }
             let config = RunningConfig {
                 status: RunningState::UpdateButNotCheck,
                 file_path: std::collections::HashMap::new(),
@@ -211,7 +466,7 @@ pub fn run_task(ui: impl UpdateUi) {
                 update_temp_path: String::new(),
                 moved_path: Vec::new(),
             };
-            update(&ui, exe_path_buf, false, config, running_config_path);
+            update(ui, options, exe_path_buf, false, config, running_config_path);
         }
         _ => {
             Log::error("获取exe_path变量错误; 程序将退出");
@@ -234,7 +489,7 @@ pub fn run_task(ui: impl UpdateUi) {
                                     Err(e) => {
                                         Log::error("打开运行状态文件失败");
                                         Log::error(e.to_string().as_str());
-                                        ui.on_failed();
+                                        ui.on_failed_with(FailReason::RunningState);
                                         return;
                                     }
                                 }
@@ -244,22 +499,23 @@ pub fn run_task(ui: impl UpdateUi) {
                                     Err(e) => {
                                         Log::error("创建运行状态文件失败");
                                         Log::error(e.to_string().as_str());
-                                        ui.on_failed();
+                                        ui.on_failed_with(FailReason::RunningState);
                                         return;
                                     }
                                 }
                             }
                         };
+                        ui.on_phase(UpdatePhase::RollingBack);
                         callback(&mut running_config_file, &mut config);
-                        ui.on_failed();
+                        ui.on_failed_with(FailReason::RolledBack);
                     }
                     Ok(config) if config.status == RunningState::Updating => {
                         let exe_path_buf = PathBuf::from(&config.exe_path);
-                        update(&ui, exe_path_buf, true, config, running_config_path);
+                        update(ui, options, exe_path_buf, true, config, running_config_path);
                     }
                     Ok(config) if config.status == RunningState::UpdateButNotCheck => {
                         let exe_path_buf = PathBuf::from(&config.exe_path);
-                        update(&ui, exe_path_buf, false, config, running_config_path);
+                        update(ui, options, exe_path_buf, false, config, running_config_path);
                     }
                     Ok(_) => {
                         Log::info("程序无执行任务");
@@ -267,7 +523,7 @@ pub fn run_task(ui: impl UpdateUi) {
                     }
                     _ => {
                         Log::error("读取运行配置失败：");
-                        ui.on_failed();
+                        ui.on_failed_with(FailReason::RunningState);
                     }
                 };
             }
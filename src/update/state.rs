@@ -1,18 +1,69 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Component, Path},
+};
 
+use serde::de::{Deserializer, Error};
 use serde_derive::{Deserialize, Serialize};
 
+/// 运行状态文件，相对于更新程序的工作目录
+pub const RUNNING_STATUS_FILE: &str = ".running_status";
+
+/// 更新配置中的一个文件，`hash` 同时是该文件在更新目录中的文件名
+///
+/// 读取更新配置时拒绝绝对路径和包含 `..` 的 `filePath`，以及包含路径分隔符的 `hash`，
+/// 避免备份、迁移和回滚时读写安装目录以外的文件
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug)]
-pub(crate) struct FileHashAndPath {
+pub struct FileHashAndPath {
+    #[serde(deserialize_with = "relative_path")]
     pub filePath: String,
+    #[serde(deserialize_with = "file_name")]
     pub hash: String,
 }
 
+/// 非空且只由普通的路径部分组成（没有根目录、盘符、`.` 和 `..`）
+pub(crate) fn is_relative_path(path: &str) -> bool {
+    let mut components = Path::new(path).components().peekable();
+    components.peek().is_some() && components.all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn relative_path<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let path: String = serde::Deserialize::deserialize(deserializer)?;
+    if is_relative_path(&path) {
+        Ok(path)
+    } else {
+        Err(D::Error::custom(format!("filePath 必须是安装目录中的相对路径: {path}")))
+    }
+}
+
+fn file_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let name: String = serde::Deserialize::deserialize(deserializer)?;
+    if is_relative_path(&name) && Path::new(&name).components().count() == 1 {
+        Ok(name)
+    } else {
+        Err(D::Error::custom(format!("hash 不能包含路径: {name}")))
+    }
+}
+
+/// 更新配置（默认为更新目录中的 `update-config.json`）
 #[derive(Serialize, Deserialize, Debug)]
-pub(crate) struct UpdateConfigJson {
+pub struct UpdateConfigJson {
+    /// 新版本的版本号，只用于显示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
     pub added: Vec<FileHashAndPath>,
     pub changed: Vec<FileHashAndPath>,
+    /// 分阶段发布的比例（0 到 100），为空时所有机器都更新；只在从更新服务器下载时生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollout: Option<f64>,
+    /// Markdown 格式的更新说明，键为语言标记（如 `zh-CN`）或 `default`
+    #[serde(
+        default,
+        rename = "releaseNotes",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub release_notes: BTreeMap<String, String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -32,3 +83,33 @@ pub struct RunningConfig {
     pub exe_path: String,
     pub moved_path: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_paths() {
+        assert!(is_relative_path("resources/app.asar"));
+        assert!(is_relative_path("electron"));
+        assert!(!is_relative_path(""));
+        assert!(!is_relative_path("/etc/passwd"));
+        assert!(!is_relative_path("../evil"));
+        assert!(!is_relative_path("resources/../../evil"));
+        assert!(!is_relative_path("./electron"));
+    }
+
+    #[test]
+    fn manifest_rejects_paths_outside_install_dir() {
+        let parse = |path: &str, hash: &str| {
+            serde_json::from_value::<FileHashAndPath>(
+                serde_json::json!({ "filePath": path, "hash": hash }),
+            )
+        };
+        assert!(parse("resources/app.asar", "abc").is_ok());
+        assert!(parse("../evil", "abc").is_err());
+        assert!(parse("/tmp/evil", "abc").is_err());
+        assert!(parse("electron", "../abc").is_err());
+        assert!(parse("electron", "dir/abc").is_err());
+    }
+}
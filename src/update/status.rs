@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use serde_derive::Serialize;
+
+use super::{
+    callbacks::FailReason,
+    maintenance::load_running_config,
+    state::{RunningConfig, RunningState},
+};
+
+/// 运行状态的含义
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusSummary {
+    /// 没有进行中的更新
+    Idle,
+    /// 更新已开始但尚未备份旧文件，下次运行会从头执行
+    PendingStart,
+    /// 更新中断，下次运行会跳过备份继续迁移文件
+    PendingResume,
+    /// 更新失败，下次运行或执行 `rollback` 时回滚
+    AwaitingRollback,
+    /// 更新已完成
+    Finished,
+}
+
+impl StatusSummary {
+    pub fn description(self) -> &'static str {
+        match self {
+            StatusSummary::Idle => "没有进行中的更新",
+            StatusSummary::PendingStart => "更新已开始但尚未备份旧文件，下次运行会从头执行",
+            StatusSummary::PendingResume => "更新中断，下次运行会继续迁移文件",
+            StatusSummary::AwaitingRollback => "更新失败，下次运行或执行 rollback 时回滚",
+            StatusSummary::Finished => "更新已完成",
+        }
+    }
+}
+
+impl From<&RunningState> for StatusSummary {
+    fn from(state: &RunningState) -> Self {
+        match state {
+            RunningState::Nothing => StatusSummary::Idle,
+            RunningState::UpdateButNotCheck => StatusSummary::PendingStart,
+            RunningState::Updating => StatusSummary::PendingResume,
+            RunningState::Failed => StatusSummary::AwaitingRollback,
+            RunningState::Finish => StatusSummary::Finished,
+        }
+    }
+}
+
+/// 已备份的旧文件
+#[derive(Serialize, Debug)]
+pub struct BackedUpFile {
+    /// 旧文件原来所在的路径
+    pub path: PathBuf,
+    pub path_exists: bool,
+    /// 旧文件的备份路径
+    pub backup_path: PathBuf,
+    pub backup_exists: bool,
+}
+
+/// 已写入的新文件
+#[derive(Serialize, Debug)]
+pub struct WrittenFile {
+    pub path: PathBuf,
+    pub exists: bool,
+}
+
+/// `.running_status` 的解读结果
+#[derive(Serialize, Debug)]
+pub struct StatusReport {
+    pub status: RunningState,
+    pub summary: StatusSummary,
+    pub description: &'static str,
+    pub exe_path: String,
+    pub update_temp_path: String,
+    pub backed_up: Vec<BackedUpFile>,
+    pub written: Vec<WrittenFile>,
+}
+
+impl StatusReport {
+    pub fn new(config: RunningConfig) -> Self {
+        let backup_dir = Path::new(&config.update_temp_path).join(".update_temp_path_old_version");
+        let mut backed_up: Vec<(usize, BackedUpFile)> = config
+            .file_path
+            .iter()
+            .map(|(index, path)| {
+                let path = PathBuf::from(path);
+                let backup_path = backup_dir.join(index.to_string());
+                let file = BackedUpFile {
+                    path_exists: path.exists(),
+                    path,
+                    backup_exists: backup_path.exists(),
+                    backup_path,
+                };
+                (*index, file)
+            })
+            .collect();
+        backed_up.sort_by_key(|(index, _)| *index);
+        let written = config
+            .moved_path
+            .iter()
+            .map(|path| WrittenFile {
+                exists: Path::new(path).exists(),
+                path: PathBuf::from(path),
+            })
+            .collect();
+        let summary = StatusSummary::from(&config.status);
+        Self {
+            summary,
+            description: summary.description(),
+            status: config.status,
+            exe_path: config.exe_path,
+            update_temp_path: config.update_temp_path,
+            backed_up: backed_up.into_iter().map(|(_, file)| file).collect(),
+            written,
+        }
+    }
+}
+
+/// 读取并解读运行状态文件，文件不存在时返回 `Ok(None)`
+pub fn inspect() -> Result<Option<StatusReport>, FailReason> {
+    Ok(load_running_config()?.map(StatusReport::new))
+}
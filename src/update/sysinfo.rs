@@ -1,40 +1,50 @@
-use std::{env, path::Path};
+use std::{env, path::Path, time::Duration};
 use sysinfo::{Pid, System};
 
 use crate::logging::{Log, Logger};
 
-/// 结束electron的进程
+/// 结束electron的进程
 ///
-/// # Examples
+/// 主进程的pid读取环境变量 `exe_pid`，每次结束进程后等待 50 毫秒；需要指定pid或等待时间时使用
+/// [`end_electron_main_with`]
 ///
-/// ```
-/// let path =  Path::new("/usr/bin/electron");
-/// let result = end_electron_main(path);
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use updater::sysinfo::end_electron_main;
 ///
-/// ```
+/// let path = Path::new("/usr/bin/electron");
+/// let result = end_electron_main(path);
+/// ```
 pub fn end_electron_main<P: AsRef<Path>>(path: P) -> bool {
+    let pid = env::var("exe_pid").ok().and_then(|pid| pid.parse().ok());
+    end_electron_main_with(path, pid, Duration::from_millis(50))
+}
+
+/// 结束electron的进程
+///
+/// `pid` 为electron主进程的pid（对应 `exe_pid`），`wait` 为每次结束进程后等待的时间
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{path::Path, time::Duration};
+/// use updater::sysinfo::end_electron_main_with;
+///
+/// let path = Path::new("/usr/bin/electron");
+/// let result = end_electron_main_with(path, None, Duration::from_millis(50));
+/// ```
+pub fn end_electron_main_with<P: AsRef<Path>>(path: P, pid: Option<usize>, wait: Duration) -> bool {
     Log::info("尝试结束进程2");
     let mut sys = System::new_all();
-    match env::var("exe_pid") {
-        Ok(pid) if pid.parse::<usize>().is_ok() => {
-            Log::info(format!("pid进程: {pid:#?}").as_str());
-
-            // []
-            match pid.parse::<usize>() {
-                Ok(pid) => {
-                    if let Some(process) = sys.process(Pid::from(pid)) {
-                        process.kill();
-                    }
-                }
-                Err(e) => {
-                    Log::error("exe_pid 解析失败");
-                    Log::error(e.to_string().as_str());
-                }
-            }
+    if let Some(pid) = pid {
+        Log::info(format!("pid进程: {pid:#?}").as_str());
+        if let Some(process) = sys.process(Pid::from(pid)) {
+            process.kill();
         }
-        _ => (),
     }
-    std::thread::sleep(std::time::Duration::from_millis(50));
+    std::thread::sleep(wait);
     sys.processes().iter().for_each(|(_pid, process)| {
         if let Some(exe) = process.exe() {
             if exe == path.as_ref() {
@@ -43,7 +53,7 @@ pub fn end_electron_main<P: AsRef<Path>>(path: P) -> bool {
             }
         }
     });
-    std::thread::sleep(std::time::Duration::from_millis(50));
+    std::thread::sleep(wait);
     sys.refresh_all();
     let still_running = sys.processes().iter().any(|(_pid, process)| {
         if let Some(exe) = process.exe() {
@@ -58,3 +68,14 @@ pub fn end_electron_main<P: AsRef<Path>>(path: P) -> bool {
     });
     !still_running
 }
+
+/// electron 是否仍在运行，`pid` 含义与 [`end_electron_main_with`] 相同
+pub fn is_electron_running<P: AsRef<Path>>(path: P, pid: Option<usize>) -> bool {
+    let sys = System::new_all();
+    if pid.is_some_and(|pid| sys.process(Pid::from(pid)).is_some()) {
+        return true;
+    }
+    sys.processes()
+        .values()
+        .any(|process| process.exe() == Some(path.as_ref()))
+}
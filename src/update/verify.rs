@@ -0,0 +1,305 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde_derive::Serialize;
+
+use crate::logging::{Log, Logger};
+
+use super::{
+    callbacks::FailReason,
+    hash::HashAlgorithm,
+    state::{FileHashAndPath, UpdateConfigJson},
+};
+
+/// 与更新配置中记录的摘要不一致的文件
+#[derive(Serialize, Debug)]
+pub struct ModifiedFile {
+    pub path: PathBuf,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// 存在但无法读取（例如没有权限）的文件
+#[derive(Serialize, Debug)]
+pub struct UnreadableFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// 安装目录的校验结果
+#[derive(Serialize, Debug, Default)]
+pub struct VerifyReport {
+    /// 通过校验的文件数
+    pub checked: usize,
+    /// 更新配置中记录但不存在的文件
+    pub missing: Vec<PathBuf>,
+    /// 内容与摘要不一致的文件
+    pub modified: Vec<ModifiedFile>,
+    /// 全量校验时，安装目录中存在但更新配置中没有记录的文件
+    pub unexpected: Vec<PathBuf>,
+    /// 摘要格式无法识别、无法校验的文件
+    pub unverifiable: Vec<PathBuf>,
+    /// 存在但读取失败的文件，与缺少的文件分开报告
+    pub unreadable: Vec<UnreadableFile>,
+}
+
+impl VerifyReport {
+    /// 所有文件都通过了校验，无法校验或无法读取的文件同样视为失败
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.modified.is_empty()
+            && self.unexpected.is_empty()
+            && self.unverifiable.is_empty()
+            && self.unreadable.is_empty()
+    }
+}
+
+/// 读取更新配置（或全量版本的更新配置）
+pub(crate) fn read_manifest(path: &Path) -> Result<UpdateConfigJson, FailReason> {
+    match serde_json::from_slice(&fs::read(path).unwrap_or_default()) {
+        Ok(config) => Ok(config),
+        Err(e) => {
+            Log::error("读取更新配置失败：");
+            Log::error(path.to_string_lossy().as_ref());
+            Log::error(e.to_string().as_str());
+            Err(FailReason::Manifest)
+        }
+    }
+}
+
+/// 按更新配置校验安装目录中的文件
+///
+/// `full` 为 `true` 时更新配置被视为全量版本清单，安装目录中未记录的文件会被报告为多余文件；
+/// `ignore` 中的路径（如暂存目录、更新程序自身）不参与多余文件的检查
+pub fn verify_install(
+    install_dir: &Path,
+    manifest: &UpdateConfigJson,
+    full: bool,
+    ignore: &[PathBuf],
+) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    let files: Vec<&FileHashAndPath> = manifest
+        .added
+        .iter()
+        .chain(manifest.changed.iter())
+        .collect();
+    for item in files.iter() {
+        let path = install_dir.join(&item.filePath);
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => {}
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                report.unreadable.push(UnreadableFile {
+                    path,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+            _ => {
+                report.missing.push(path);
+                continue;
+            }
+        }
+        let Some(algorithm) = HashAlgorithm::detect(&item.hash) else {
+            report.unverifiable.push(path);
+            continue;
+        };
+        match algorithm.hash_file(&path) {
+            Ok(actual) if actual.eq_ignore_ascii_case(&item.hash) => report.checked += 1,
+            Ok(actual) => report.modified.push(ModifiedFile {
+                path,
+                expected: item.hash.to_lowercase(),
+                actual,
+            }),
+            Err(e) => {
+                Log::error("读取文件失败");
+                Log::error(path.to_string_lossy().as_ref());
+                Log::error(e.to_string().as_str());
+                report.unreadable.push(UnreadableFile {
+                    path,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    if full {
+        let listed: HashSet<String> = files.iter().map(|item| normalize(&item.filePath)).collect();
+        collect_unexpected(install_dir, install_dir, &listed, ignore, &mut report.unexpected);
+    }
+    report
+}
+
+fn collect_unexpected(
+    root: &Path,
+    dir: &Path,
+    listed: &HashSet<String>,
+    ignore: &[PathBuf],
+    unexpected: &mut Vec<PathBuf>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            Log::error("读取目录失败");
+            Log::error(dir.to_string_lossy().as_ref());
+            Log::error(e.to_string().as_str());
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if ignore.iter().any(|ignored| ignored == &path) {
+            continue;
+        }
+        // 不跟随符号链接，避免重复检查或循环（如 macOS 应用包中的 Versions/Current）
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            collect_unexpected(root, &path, listed, ignore, unexpected);
+            continue;
+        }
+        if file_type.is_symlink() && path.is_dir() {
+            continue;
+        }
+        let relative = match path.strip_prefix(root) {
+            Ok(relative) => normalize(&relative.to_string_lossy()),
+            Err(_) => continue,
+        };
+        if !listed.contains(&relative) {
+            unexpected.push(path);
+        }
+    }
+}
+
+/// 统一为 `/` 分隔、不带开头 `./` 的相对路径，便于与更新配置中的 `filePath` 比较
+pub(crate) fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.trim_start_matches("./")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::update::{hash::to_hex, test_support::TempDir};
+
+    fn sha256(content: &[u8]) -> String {
+        to_hex(&Sha256::digest(content))
+    }
+
+    fn manifest(files: &[(&str, String)]) -> UpdateConfigJson {
+        UpdateConfigJson {
+            version: None,
+            added: Vec::new(),
+            changed: files
+                .iter()
+                .map(|(path, hash)| FileHashAndPath {
+                    filePath: path.to_string(),
+                    hash: hash.clone(),
+                })
+                .collect(),
+            rollout: None,
+            release_notes: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn reports_missing_modified_and_unexpected() {
+        let dir = TempDir::new("verify");
+        dir.write("electron", b"new");
+        dir.write("resources/app.asar", b"changed locally");
+        dir.write("resources/extra.txt", b"extra");
+        let manifest = manifest(&[
+            ("electron", sha256(b"new")),
+            ("resources/app.asar", sha256(b"asar")),
+            ("resources/missing.pak", sha256(b"pak")),
+        ]);
+
+        let report = verify_install(dir.path(), &manifest, false, &[]);
+        assert_eq!(report.checked, 1);
+        assert_eq!(report.missing, vec![dir.path().join("resources/missing.pak")]);
+        assert_eq!(report.modified.len(), 1);
+        assert_eq!(report.modified[0].path, dir.path().join("resources/app.asar"));
+        assert!(report.unexpected.is_empty());
+        assert!(!report.is_ok());
+
+        let report = verify_install(dir.path(), &manifest, true, &[]);
+        assert_eq!(report.unexpected, vec![dir.path().join("resources/extra.txt")]);
+        let ignore = [dir.path().join("resources/extra.txt")];
+        let report = verify_install(dir.path(), &manifest, true, &ignore);
+        assert!(report.unexpected.is_empty());
+    }
+
+    #[test]
+    fn unknown_hash_is_not_ok() {
+        let dir = TempDir::new("verify");
+        dir.write("electron", b"new");
+        let report = verify_install(
+            dir.path(),
+            &manifest(&[("electron", "not-a-hash".to_string())]),
+            false,
+            &[],
+        );
+        assert_eq!(report.checked, 0);
+        assert_eq!(report.unverifiable, vec![dir.path().join("electron")]);
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn matching_install_is_ok() {
+        let dir = TempDir::new("verify");
+        dir.write("electron", b"new");
+        let report =
+            verify_install(dir.path(), &manifest(&[("./electron", sha256(b"new"))]), true, &[]);
+        assert_eq!(report.checked, 1);
+        assert!(report.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reports_unreadable_files_separately() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("verify");
+        let path = dir.write("electron", b"new");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::read(&path).is_ok() {
+            // 以 root 运行时权限不影响读取
+            return;
+        }
+        let report =
+            verify_install(dir.path(), &manifest(&[("electron", sha256(b"new"))]), false, &[]);
+        assert!(report.missing.is_empty());
+        assert_eq!(report.unreadable.len(), 1);
+        assert_eq!(report.unreadable[0].path, path);
+        assert!(!report.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_directory_symlinks() {
+        let dir = TempDir::new("verify");
+        dir.write("Versions/A/electron", b"new");
+        std::os::unix::fs::symlink("A", dir.path().join("Versions/Current")).unwrap();
+        let report = verify_install(
+            dir.path(),
+            &manifest(&[("Versions/A/electron", sha256(b"new"))]),
+            true,
+            &[],
+        );
+        assert!(report.is_ok(), "{report:?}");
+    }
+
+    #[test]
+    fn normalizes_separators() {
+        assert_eq!(normalize("./resources\\app.asar"), "resources/app.asar");
+        assert_eq!(normalize("/electron"), "electron");
+    }
+}
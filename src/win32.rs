@@ -0,0 +1,26 @@
+//! 使用的 Windows API，所有 kernel32 的声明都放在这里
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn AttachConsole(process_id: u32) -> i32;
+    fn GetUserDefaultLocaleName(name: *mut u16, len: i32) -> i32;
+}
+
+const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+const LOCALE_NAME_MAX_LENGTH: usize = 85;
+
+/// 连接父进程的控制台，已经有控制台或父进程没有控制台时调用失败，忽略即可
+pub(crate) fn attach_parent_console() {
+    // SAFETY: AttachConsole 只接收一个整数参数
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// 用户的语言设置，例如 `zh-CN`
+pub(crate) fn user_default_locale_name() -> Option<String> {
+    let mut buffer = [0u16; LOCALE_NAME_MAX_LENGTH];
+    // SAFETY: 缓冲区的长度与传入的 len 一致；返回值包含结尾的 0，失败时为 0
+    let len = unsafe { GetUserDefaultLocaleName(buffer.as_mut_ptr(), buffer.len() as i32) };
+    (len > 1).then(|| String::from_utf16_lossy(&buffer[..len as usize - 1]))
+}